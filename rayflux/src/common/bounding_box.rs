@@ -0,0 +1,405 @@
+use glam::{DMat4, DVec3};
+
+use crate::common::{shapes::{Cone, Csg, Cube, Cylinder, Disk, Group, Heightfield, Plane, Quad, Shape, SmoothTriangle, Sphere, Torus, Triangle}, Object, Sdf};
+
+/// An axis aligned box, its bounds can be infinite for the shapes extending forever
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    min: DVec3,
+    max: DVec3,
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl BoundingBox {
+    /// Contains nothing, the neutral element of `union`
+    pub const EMPTY: Self = BoundingBox {
+        min: DVec3::INFINITY,
+        max: DVec3::NEG_INFINITY,
+    };
+
+    pub const INFINITE: Self = BoundingBox {
+        min: DVec3::NEG_INFINITY,
+        max: DVec3::INFINITY,
+    };
+
+    pub fn new(min: DVec3, max: DVec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points(points: &[DVec3]) -> Self {
+        points.iter().fold(Self::EMPTY, |bounds, point| bounds.with_point(*point))
+    }
+
+    pub fn min(&self) -> DVec3 {
+        self.min
+    }
+
+    pub fn max(&self) -> DVec3 {
+        self.max
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min.is_finite() && self.max.is_finite()
+    }
+
+    pub fn with_point(self, point: DVec3) -> Self {
+        Self {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn contains_point(&self, point: DVec3) -> bool {
+        self.min.cmple(point).all() && point.cmple(self.max).all()
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    pub fn centroid(&self) -> DVec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let size = self.max - self.min;
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    /// Axis aligned box containing this box once transformed by a matrix
+    pub fn transform(&self, matrix: &DMat4) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+
+        // Each coordinate of the result is the translation plus the extremes of every term of the matrix product,
+        // the null terms are skipped so that infinite bounds don't turn into NaN
+        let mut min = matrix.w_axis.truncate();
+        let mut max = min;
+        for (axis, (low, high)) in [matrix.x_axis, matrix.y_axis, matrix.z_axis]
+            .iter()
+            .zip(self.min.to_array().into_iter().zip(self.max.to_array()))
+        {
+            for row in 0..3 {
+                let coefficient = axis[row];
+                if coefficient == 0.0 {
+                    continue;
+                }
+                let a = coefficient * low;
+                let b = coefficient * high;
+                min[row] += a.min(b);
+                max[row] += a.max(b);
+            }
+        }
+        Self { min, max }
+    }
+}
+
+impl Sphere {
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(DVec3::NEG_ONE, DVec3::ONE)
+    }
+}
+
+impl Plane {
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            DVec3::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            DVec3::new(f64::INFINITY, 0.0, f64::INFINITY),
+        )
+    }
+}
+
+impl Cube {
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(DVec3::NEG_ONE, DVec3::ONE)
+    }
+}
+
+impl Cylinder {
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(DVec3::new(-1.0, self.min, -1.0), DVec3::new(1.0, self.max, 1.0))
+    }
+}
+
+impl Cone {
+    pub fn bounds(&self) -> BoundingBox {
+        let radius = self.min.abs().max(self.max.abs());
+        BoundingBox::new(DVec3::new(-radius, self.min, -radius), DVec3::new(radius, self.max, radius))
+    }
+}
+
+impl Torus {
+    pub fn bounds(&self) -> BoundingBox {
+        let radius = self.major_radius + self.minor_radius;
+        BoundingBox::new(
+            DVec3::new(-radius, -self.minor_radius, -radius),
+            DVec3::new(radius, self.minor_radius, radius),
+        )
+    }
+}
+
+impl Disk {
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(DVec3::new(-self.radius, 0.0, -self.radius), DVec3::new(self.radius, 0.0, self.radius))
+    }
+}
+
+impl Quad {
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::from_points(&[
+            self.corner(),
+            self.corner() + self.edge1(),
+            self.corner() + self.edge2(),
+            self.corner() + self.edge1() + self.edge2(),
+        ])
+    }
+}
+
+impl Sdf {
+    /// Box containing the inside of the function
+    pub fn bounds(&self) -> BoundingBox {
+        match self {
+            Sdf::Sphere { radius } => BoundingBox::new(DVec3::splat(-radius), DVec3::splat(*radius)),
+            Sdf::Cuboid { half_extents } => BoundingBox::new(-*half_extents, *half_extents),
+            Sdf::Torus { major_radius, minor_radius } => Torus::new(*major_radius, *minor_radius).bounds(),
+            Sdf::Capsule { a, b, radius } => {
+                let bounds = BoundingBox::from_points(&[*a, *b]);
+                BoundingBox::new(bounds.min() - *radius, bounds.max() + *radius)
+            },
+            Sdf::Translate(sdf, offset) => sdf.bounds().transform(&DMat4::from_translation(*offset)),
+            Sdf::SmoothUnion(a, b, k) => {
+                // The blend lowers the distance by k / 4 at most
+                let bounds = a.bounds().union(&b.bounds());
+                BoundingBox::new(bounds.min() - k * 0.25, bounds.max() + k * 0.25)
+            },
+            Sdf::Subtraction(a, _) => a.bounds(),
+            Sdf::Repeat(sdf, period) => {
+                let bounds = sdf.bounds();
+                let repeated = period.cmpgt(DVec3::ZERO);
+                BoundingBox::new(
+                    DVec3::select(repeated, DVec3::NEG_INFINITY, bounds.min()),
+                    DVec3::select(repeated, DVec3::INFINITY, bounds.max()),
+                )
+            },
+        }
+    }
+}
+
+impl Heightfield {
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(DVec3::new(0.0, self.min_height(), 0.0), DVec3::new(1.0, self.max_height(), 1.0))
+    }
+}
+
+impl Triangle {
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::from_points(&[self.p1(), self.p2(), self.p3()])
+    }
+}
+
+impl SmoothTriangle {
+    pub fn bounds(&self) -> BoundingBox {
+        self.triangle().bounds()
+    }
+}
+
+impl Group {
+    pub fn bounds(&self) -> BoundingBox {
+        self.children()
+            .iter()
+            .fold(BoundingBox::EMPTY, |bounds, child| bounds.union(&child.parent_space_bounds()))
+    }
+}
+
+impl Csg {
+    pub fn bounds(&self) -> BoundingBox {
+        self.left().parent_space_bounds().union(&self.right().parent_space_bounds())
+    }
+}
+
+impl Shape {
+    /// Bounds of the shape in object space
+    pub fn bounds(&self) -> BoundingBox {
+        match self {
+            Shape::Sphere(sphere) => sphere.bounds(),
+            Shape::Plane(plane) => plane.bounds(),
+            Shape::Cube(cube) => cube.bounds(),
+            Shape::Cylinder(cylinder) => cylinder.bounds(),
+            Shape::Cone(cone) => cone.bounds(),
+            Shape::Torus(torus) => torus.bounds(),
+            Shape::Disk(disk) => disk.bounds(),
+            Shape::Quad(quad) => quad.bounds(),
+            Shape::Sdf(sdf) => sdf.bounds(),
+            Shape::Heightfield(heightfield) => heightfield.bounds(),
+            Shape::Triangle(triangle) => triangle.bounds(),
+            Shape::SmoothTriangle(triangle) => triangle.bounds(),
+            Shape::Group(group) => group.bounds(),
+            Shape::Csg(csg) => csg.bounds(),
+        }
+    }
+}
+
+impl Object {
+    /// Bounds of the object in the space of its parent group, which is world space for a top level object
+    pub fn parent_space_bounds(&self) -> BoundingBox {
+        self.shape().bounds().transform(&self.transform().matrix())
+    }
+
+    /// Bounds of the object in world space, through every parent group
+    pub fn bounds(&self) -> BoundingBox {
+        self.shape().bounds().transform(&self.world_inverse_matrix().inverse())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_4;
+
+    use glam::dvec3;
+    use crate::common::Transform;
+    use super::*;
+
+    const EPSILON: f64 = 0.0001;
+
+    #[test]
+    fn creating_an_empty_bounding_box() {
+        let bb = BoundingBox::default();
+        assert_eq!(bb.min(), DVec3::INFINITY);
+        assert_eq!(bb.max(), DVec3::NEG_INFINITY);
+        assert!(bb.is_empty());
+    }
+
+    #[test]
+    fn adding_points_to_an_empty_bounding_box() {
+        let bb = BoundingBox::default()
+            .with_point(dvec3(-5.0, 2.0, 0.0))
+            .with_point(dvec3(7.0, 0.0, -3.0));
+        assert_eq!(bb.min(), dvec3(-5.0, 0.0, -3.0));
+        assert_eq!(bb.max(), dvec3(7.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn adding_one_bounding_box_to_another() {
+        let box1 = BoundingBox::new(dvec3(-5.0, -2.0, 0.0), dvec3(7.0, 4.0, 4.0));
+        let box2 = BoundingBox::new(dvec3(8.0, -7.0, -2.0), dvec3(14.0, 2.0, 8.0));
+        let bb = box1.union(&box2);
+        assert_eq!(bb.min(), dvec3(-5.0, -7.0, -2.0));
+        assert_eq!(bb.max(), dvec3(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn checking_to_see_if_a_box_contains_a_given_point() {
+        let bb = BoundingBox::new(dvec3(5.0, -2.0, 0.0), dvec3(11.0, 4.0, 7.0));
+        let examples = [
+            (dvec3(5.0, -2.0, 0.0), true),
+            (dvec3(11.0, 4.0, 7.0), true),
+            (dvec3(8.0, 1.0, 3.0), true),
+            (dvec3(3.0, 0.0, 3.0), false),
+            (dvec3(8.0, -4.0, 3.0), false),
+            (dvec3(8.0, 1.0, -1.0), false),
+            (dvec3(13.0, 1.0, 3.0), false),
+            (dvec3(8.0, 5.0, 3.0), false),
+            (dvec3(8.0, 1.0, 8.0), false),
+        ];
+        for (point, result) in examples {
+            assert_eq!(bb.contains_point(point), result);
+        }
+    }
+
+    #[test]
+    fn checking_to_see_if_a_box_contains_a_given_box() {
+        let bb = BoundingBox::new(dvec3(5.0, -2.0, 0.0), dvec3(11.0, 4.0, 7.0));
+        let examples = [
+            (dvec3(5.0, -2.0, 0.0), dvec3(11.0, 4.0, 7.0), true),
+            (dvec3(6.0, -1.0, 1.0), dvec3(10.0, 3.0, 6.0), true),
+            (dvec3(4.0, -3.0, -1.0), dvec3(10.0, 3.0, 6.0), false),
+            (dvec3(6.0, -1.0, 1.0), dvec3(12.0, 5.0, 8.0), false),
+        ];
+        for (min, max, result) in examples {
+            assert_eq!(bb.contains_box(&BoundingBox::new(min, max)), result);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn transforming_a_bounding_box() {
+        let bb = BoundingBox::new(dvec3(-1.0, -1.0, -1.0), dvec3(1.0, 1.0, 1.0));
+        let matrix = DMat4::from_rotation_x(FRAC_PI_4) * DMat4::from_rotation_y(FRAC_PI_4);
+        let bb = bb.transform(&matrix);
+        assert!(bb.min().abs_diff_eq(dvec3(-1.4142, -1.7071, -1.7071), EPSILON));
+        assert!(bb.max().abs_diff_eq(dvec3(1.4142, 1.7071, 1.7071), EPSILON));
+    }
+
+    #[test]
+    fn transforming_infinite_bounds_keeps_the_finite_axes() {
+        let bb = Plane::default().bounds().transform(&DMat4::from_translation(dvec3(0.0, 2.0, 0.0)));
+        assert_eq!(bb.min(), dvec3(f64::NEG_INFINITY, 2.0, f64::NEG_INFINITY));
+        assert_eq!(bb.max(), dvec3(f64::INFINITY, 2.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn shapes_have_bounding_boxes() {
+        assert_eq!(Cube::default().bounds(), BoundingBox::new(dvec3(-1.0, -1.0, -1.0), dvec3(1.0, 1.0, 1.0)));
+        assert_eq!(
+            Cylinder { min: f64::NEG_INFINITY, max: f64::INFINITY, closed: false }.bounds(),
+            BoundingBox::new(dvec3(-1.0, f64::NEG_INFINITY, -1.0), dvec3(1.0, f64::INFINITY, 1.0))
+        );
+        assert_eq!(
+            Cylinder { min: -5.0, max: 3.0, closed: false }.bounds(),
+            BoundingBox::new(dvec3(-1.0, -5.0, -1.0), dvec3(1.0, 3.0, 1.0))
+        );
+        assert!(!Cone::default().bounds().is_finite());
+        assert_eq!(
+            Cone { min: -5.0, max: 3.0, closed: false }.bounds(),
+            BoundingBox::new(dvec3(-5.0, -5.0, -5.0), dvec3(5.0, 3.0, 5.0))
+        );
+        assert_eq!(
+            Triangle::new(dvec3(-3.0, 7.0, 2.0), dvec3(6.0, 2.0, -4.0), dvec3(2.0, -1.0, -1.0)).bounds(),
+            BoundingBox::new(dvec3(-3.0, -1.0, -4.0), dvec3(6.0, 7.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn a_group_has_a_bounding_box_that_contains_its_children() {
+        let s = Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(2.0, 5.0, -3.0)).with_scale(dvec3(2.0, 2.0, 2.0)));
+        let c = Object::new(Shape::Cylinder(Cylinder { min: -2.0, max: 2.0, closed: false }))
+            .with_transform(Transform::from_translation(dvec3(-4.0, -1.0, 4.0)).with_scale(dvec3(0.5, 1.0, 0.5)));
+        let g = Object::new_group(vec![s, c]);
+        let bb = g.shape().bounds();
+        assert_eq!(bb.min(), dvec3(-4.5, -3.0, -5.0));
+        assert_eq!(bb.max(), dvec3(4.0, 7.0, 4.5));
+    }
+
+    #[test]
+    fn an_object_has_world_space_bounds_through_its_groups() {
+        let s = Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(5.0, 0.0, 0.0)));
+        let g = Object::new_group(vec![s])
+            .with_transform(Transform::from_scale(dvec3(2.0, 2.0, 2.0)));
+        let bb = g.children()[0].bounds();
+        assert!(bb.min().abs_diff_eq(dvec3(8.0, -2.0, -2.0), EPSILON));
+        assert!(bb.max().abs_diff_eq(dvec3(12.0, 2.0, 2.0), EPSILON));
+        assert_eq!(bb, g.bounds());
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use crate::common::{ImageTexture, Pattern, UvMapping};
+
+/// Perturbation of the shading normal, adding surface detail without changing the geometry
+#[derive(Debug, Clone, PartialEq)]
+pub enum BumpMap {
+    /// Procedural height field, the brightness of the pattern being the height of the surface.
+    /// `scale` converts that brightness to a distance in object space.
+    Height {
+        pattern: Box<Pattern>,
+        scale: f64,
+    },
+    /// Tangent-space normal map, red and green tilting the normal towards increasing u and v.
+    /// `strength` scales the tilt, 1 using the texture as is.
+    NormalMap {
+        texture: Arc<ImageTexture>,
+        mapping: UvMapping,
+        strength: f64,
+    },
+}
+
+impl BumpMap {
+    pub fn new_height(pattern: Pattern, scale: f64) -> Self {
+        Self::Height { pattern: Box::new(pattern), scale }
+    }
+
+    pub fn new_normal_map(texture: Arc<ImageTexture>, mapping: UvMapping) -> Self {
+        Self::NormalMap { texture, mapping, strength: 1.0 }
+    }
+
+    /// Scale the effect of the map, flattening it below 1 and exaggerating it above
+    pub fn with_strength(mut self, value: f64) -> Self {
+        match &mut self {
+            Self::Height { scale, .. } => *scale = value,
+            Self::NormalMap { strength, .. } => *strength = value,
+        }
+        self
+    }
+}
@@ -0,0 +1,172 @@
+use crate::common::{BoundingBox, Object};
+
+/// Number of buckets the centroids are sorted into when looking for the best split
+const SAH_BUCKETS: usize = 12;
+/// Cost of visiting a node relatively to the cost of intersecting an object
+const TRAVERSAL_COST: f64 = 0.5;
+/// Above this number of objects a node is always split
+const MAX_LEAF_SIZE: usize = 8;
+
+/// Bounding volume hierarchy over the objects of a scene or the children of a group, built with the surface area heuristic.
+/// The objects are bounded in the space of their parent, and the ones with infinite bounds, like planes, are kept out of the tree.
+#[derive(Debug, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    /// Object indices, each leaf referencing a contiguous range
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+#[derive(Debug)]
+pub struct BvhNode {
+    pub bounds: BoundingBox,
+    pub kind: BvhNodeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BvhNodeKind {
+    /// Range of `Bvh::indices` holding the objects of the leaf
+    Leaf { first: usize, count: usize },
+    /// Indices of the child nodes
+    Interior { left: usize, right: usize },
+}
+
+/// An object taking part in the build
+struct BuildItem {
+    index: usize,
+    bounds: BoundingBox,
+    centroid: glam::DVec3,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    count: usize,
+    bounds: BoundingBox,
+}
+
+impl Bvh {
+    pub fn build(objects: &[Object]) -> Self {
+        let mut bvh = Self::default();
+        let mut items = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            let bounds = object.parent_space_bounds();
+            if bounds.is_empty() {
+                continue;
+            }
+            if bounds.is_finite() {
+                items.push(BuildItem { index, bounds, centroid: bounds.centroid() });
+            } else {
+                bvh.unbounded.push(index);
+            }
+        }
+
+        if !items.is_empty() {
+            bvh.build_node(&mut items);
+        }
+        bvh
+    }
+
+    /// Root node first, none when there is no bounded object
+    pub fn nodes(&self) -> &Vec<BvhNode> {
+        &self.nodes
+    }
+
+    /// Object indices of a leaf
+    pub fn leaf_objects(&self, first: usize, count: usize) -> &[usize] {
+        &self.indices[first..first + count]
+    }
+
+    /// Indices of the objects with infinite bounds, that must be tested against every ray
+    pub fn unbounded(&self) -> &Vec<usize> {
+        &self.unbounded
+    }
+
+    /// Append the node of a set of items and its descendants, returning its index
+    fn build_node(&mut self, items: &mut [BuildItem]) -> usize {
+        let bounds = items.iter().fold(BoundingBox::EMPTY, |bounds, item| bounds.union(&item.bounds));
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode { bounds, kind: BvhNodeKind::Leaf { first: 0, count: 0 } });
+
+        match Self::find_split(items, &bounds) {
+            Some(split) => {
+                let (left_items, right_items) = items.split_at_mut(split);
+                let left = self.build_node(left_items);
+                let right = self.build_node(right_items);
+                self.nodes[node_index].kind = BvhNodeKind::Interior { left, right };
+            },
+            None => {
+                let first = self.indices.len();
+                self.indices.extend(items.iter().map(|item| item.index));
+                self.nodes[node_index].kind = BvhNodeKind::Leaf { first, count: items.len() };
+            },
+        }
+        node_index
+    }
+
+    /// Partition the items along the cheapest split according to the surface area heuristic
+    /// and return the size of the first part, or None when a leaf is cheaper
+    fn find_split(items: &mut [BuildItem], bounds: &BoundingBox) -> Option<usize> {
+        if items.len() <= 2 {
+            return None;
+        }
+
+        let centroid_bounds = items.iter().fold(BoundingBox::EMPTY, |bounds, item| bounds.with_point(item.centroid));
+        let extent = centroid_bounds.max() - centroid_bounds.min();
+
+        // (cost, axis, last bucket of the first part)
+        let mut best: Option<(f64, usize, usize)> = None;
+        for axis in 0..3 {
+            if extent[axis] <= 0.0 {
+                continue;
+            }
+
+            let bucket_of = |item: &BuildItem| bucket(item.centroid[axis], centroid_bounds.min()[axis], extent[axis]);
+
+            let mut buckets = [Bucket { count: 0, bounds: BoundingBox::EMPTY }; SAH_BUCKETS];
+            for item in items.iter() {
+                let bucket = &mut buckets[bucket_of(item)];
+                bucket.count += 1;
+                bucket.bounds = bucket.bounds.union(&item.bounds);
+            }
+
+            // Sweep from the right to know the cost of every possible second part
+            let mut right_costs = [0.0; SAH_BUCKETS];
+            let mut right = Bucket { count: 0, bounds: BoundingBox::EMPTY };
+            for split in (1..SAH_BUCKETS).rev() {
+                right.count += buckets[split].count;
+                right.bounds = right.bounds.union(&buckets[split].bounds);
+                right_costs[split - 1] = right.count as f64 * right.bounds.surface_area();
+            }
+
+            let mut left = Bucket { count: 0, bounds: BoundingBox::EMPTY };
+            for split in 0..SAH_BUCKETS - 1 {
+                left.count += buckets[split].count;
+                left.bounds = left.bounds.union(&buckets[split].bounds);
+                if left.count == 0 || left.count == items.len() {
+                    continue;
+                }
+                let cost = TRAVERSAL_COST
+                    + (left.count as f64 * left.bounds.surface_area() + right_costs[split]) / bounds.surface_area();
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, axis, split));
+                }
+            }
+        }
+
+        let (cost, axis, split) = best?;
+        let leaf_cost = items.len() as f64;
+        if cost >= leaf_cost && items.len() <= MAX_LEAF_SIZE {
+            return None;
+        }
+
+        let bucket_of = |item: &BuildItem| bucket(item.centroid[axis], centroid_bounds.min()[axis], extent[axis]);
+        items.sort_unstable_by_key(bucket_of);
+        Some(items.partition_point(|item| bucket_of(item) <= split))
+    }
+}
+
+/// Bucket of a centroid coordinate, given the range covered by all the centroids
+fn bucket(coordinate: f64, min: f64, extent: f64) -> usize {
+    let offset = (coordinate - min) / extent;
+    ((offset * SAH_BUCKETS as f64) as usize).min(SAH_BUCKETS - 1)
+}
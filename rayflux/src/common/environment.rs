@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use crate::common::{ImageTexture, TextureWrap, Transform};
+
+/// An equirectangular image surrounding the scene, seen by the rays that miss every object
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvironmentMap {
+    texture: ImageTexture,
+    transform: Transform,
+    intensity: f64,
+}
+
+impl EnvironmentMap {
+    pub fn new(texture: ImageTexture) -> Self {
+        Self {
+            // Wraps around horizontally, but the poles must not bleed into each other
+            texture: texture.with_wrap_uv(TextureWrap::Repeat, TextureWrap::Clamp),
+            transform: Transform::default(),
+            intensity: 1.0,
+        }
+    }
+
+    /// Load an environment from an image file, usually a `.hdr` one
+    pub fn load<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        Ok(Self::new(ImageTexture::load(path)?))
+    }
+
+    /// Orient the environment, only the rotation is meaningful
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self.transform.update_matrix();
+        self
+    }
+
+    /// Scale the radiance of the environment
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn texture(&self) -> &ImageTexture {
+        &self.texture
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+}
@@ -0,0 +1,205 @@
+use std::{fmt, path::Path};
+
+use glam::DVec2;
+
+use crate::common::color::Color;
+
+/// How texels are combined when sampling a texture
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TextureFilter {
+    /// Use the closest texel
+    Nearest,
+    /// Interpolate between the four closest texels
+    #[default]
+    Bilinear,
+}
+
+/// What happens to texture coordinates outside of the [0, 1] range
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TextureWrap {
+    /// The texture is tiled
+    #[default]
+    Repeat,
+    /// The texels on the border are stretched
+    Clamp,
+}
+
+impl TextureWrap {
+    /// Bring a texel coordinate back in [0, size[
+    fn apply(&self, coordinate: i64, size: u32) -> i64 {
+        match self {
+            TextureWrap::Repeat => coordinate.rem_euclid(size as i64),
+            TextureWrap::Clamp => coordinate.clamp(0, size as i64 - 1),
+        }
+    }
+}
+
+/// A bitmap texture sampled with uv coordinates
+#[derive(Clone, PartialEq)]
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    /// Row major, starting from the top row
+    pixels: Vec<Color>,
+    filter: TextureFilter,
+    wrap_u: TextureWrap,
+    wrap_v: TextureWrap,
+}
+
+impl ImageTexture {
+    pub fn new(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), (width * height) as usize, "pixel count does not match texture size");
+        Self {
+            width,
+            height,
+            pixels,
+            filter: TextureFilter::default(),
+            wrap_u: TextureWrap::default(),
+            wrap_v: TextureWrap::default(),
+        }
+    }
+
+    /// Load a texture from any image format supported by the image crate
+    pub fn load<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        let image = image::open(path)?.to_rgb32f();
+        let pixels = image
+            .pixels()
+            .map(|p| Color::new(p[0] as f64, p[1] as f64, p[2] as f64))
+            .collect();
+
+        Ok(Self::new(image.width(), image.height(), pixels))
+    }
+
+    pub fn with_filter(mut self, filter: TextureFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Use the same wrap mode on both axes
+    pub fn with_wrap(mut self, wrap: TextureWrap) -> Self {
+        self.wrap_u = wrap;
+        self.wrap_v = wrap;
+        self
+    }
+
+    pub fn with_wrap_uv(mut self, wrap_u: TextureWrap, wrap_v: TextureWrap) -> Self {
+        self.wrap_u = wrap_u;
+        self.wrap_v = wrap_v;
+        self
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn filter(&self) -> TextureFilter {
+        self.filter
+    }
+
+    pub fn wrap_u(&self) -> TextureWrap {
+        self.wrap_u
+    }
+
+    pub fn wrap_v(&self) -> TextureWrap {
+        self.wrap_v
+    }
+
+    /// Get a texel, applying the wrap modes to out of range coordinates
+    pub fn texel(&self, x: i64, y: i64) -> Color {
+        let x = self.wrap_u.apply(x, self.width);
+        let y = self.wrap_v.apply(y, self.height);
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Sample the texture at uv coordinates, v going from the bottom (0) to the top (1) of the image
+    pub fn sample(&self, uv: DVec2) -> Color {
+        let x = uv.x * self.width as f64;
+        let y = (1.0 - uv.y) * self.height as f64;
+
+        match self.filter {
+            TextureFilter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            TextureFilter::Bilinear => {
+                // Texel centers are at half coordinates
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let fx = x - x0;
+                let fy = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+                let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            },
+        }
+    }
+}
+
+impl fmt::Debug for ImageTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageTexture")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("filter", &self.filter)
+            .field("wrap_u", &self.wrap_u)
+            .field("wrap_v", &self.wrap_v)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec2;
+
+    use super::*;
+
+    fn checker_texture() -> ImageTexture {
+        // 2x2 texture
+        // RED   GREEN
+        // BLUE  WHITE
+        ImageTexture::new(2, 2, vec![Color::RED, Color::GREEN, Color::BLUE, Color::WHITE])
+    }
+
+    #[test]
+    fn nearest_sampling_picks_the_closest_texel() {
+        let t = checker_texture().with_filter(TextureFilter::Nearest);
+        assert_eq!(t.sample(dvec2(0.25, 0.75)), Color::RED);
+        assert_eq!(t.sample(dvec2(0.75, 0.75)), Color::GREEN);
+        assert_eq!(t.sample(dvec2(0.25, 0.25)), Color::BLUE);
+        assert_eq!(t.sample(dvec2(0.75, 0.25)), Color::WHITE);
+    }
+
+    #[test]
+    fn bilinear_sampling_interpolates_texels() {
+        let t = checker_texture()
+            .with_filter(TextureFilter::Bilinear)
+            .with_wrap(TextureWrap::Clamp);
+        // On a texel center
+        assert_eq!(t.sample(dvec2(0.25, 0.75)), Color::RED);
+        // Between the two top texels
+        assert_eq!(t.sample(dvec2(0.5, 0.75)), Color::new(0.5, 0.5, 0.0));
+        // At the center of the texture
+        assert_eq!(t.sample(dvec2(0.5, 0.5)), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn repeat_wrap_tiles_the_texture() {
+        let t = checker_texture().with_filter(TextureFilter::Nearest);
+        assert_eq!(t.sample(dvec2(1.25, 0.75)), Color::RED);
+        assert_eq!(t.sample(dvec2(-0.25, -0.75)), Color::WHITE);
+    }
+
+    #[test]
+    fn clamp_wrap_stretches_the_border() {
+        let t = checker_texture()
+            .with_filter(TextureFilter::Nearest)
+            .with_wrap(TextureWrap::Clamp);
+        assert_eq!(t.sample(dvec2(1.25, 0.75)), Color::GREEN);
+        assert_eq!(t.sample(dvec2(-3.0, -2.0)), Color::BLUE);
+    }
+}
@@ -0,0 +1,421 @@
+use glam::{DVec2, DVec3};
+
+use crate::common::color::Color;
+
+/// The lights of a scene
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    Point(PointLight),
+    Area(AreaLight),
+    Spot(SpotLight),
+    Directional(DirectionalLight),
+}
+
+/// How the intensity of a light decreases with the distance
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Attenuation {
+    /// Same intensity at any distance
+    #[default]
+    None,
+    /// Physically based falloff, the intensity is divided by the squared distance
+    InverseSquare,
+}
+
+impl Attenuation {
+    pub fn factor(&self, distance: f64) -> f64 {
+        match self {
+            Attenuation::None => 1.0,
+            Attenuation::InverseSquare => 1.0 / (distance * distance),
+        }
+    }
+}
+
+/// A light as seen from a point of the scene
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSample {
+    /// Normalized direction from the point toward the light
+    pub direction: DVec3,
+    /// Distance to the light, infinite for directional lights
+    pub distance: f64,
+    /// Intensity reaching the point, ignoring occlusion
+    pub intensity: Color,
+}
+
+impl Light {
+    /// Unattenuated intensity of the light
+    pub fn intensity(&self) -> Color {
+        match self {
+            Light::Point(light) => light.intensity,
+            Light::Area(light) => light.intensity,
+            Light::Spot(light) => light.intensity,
+            Light::Directional(light) => light.intensity,
+        }
+    }
+
+    /// Number of positions to sample on the light, only area lights have more than one
+    pub fn sample_count(&self) -> u32 {
+        match self {
+            Light::Area(light) => light.samples(),
+            _ => 1,
+        }
+    }
+
+    /// Whether the sample positions should be randomized
+    pub fn jitter(&self) -> bool {
+        match self {
+            Light::Area(light) => light.jitter,
+            _ => false,
+        }
+    }
+
+    /// Sample the light from a point.
+    /// For area lights, `index` selects a cell and `offset` the position inside it, in [0, 1[.
+    /// Other lights ignore both.
+    pub fn sample(&self, point: DVec3, index: u32, offset: DVec2) -> LightSample {
+        match self {
+            Light::Point(light) => light.sample(point),
+            Light::Area(light) => {
+                let u = index % light.usteps;
+                let v = index / light.usteps;
+                let position = light.point_on_light(u, v, offset.x, offset.y);
+                sample_position(point, position, light.intensity, light.attenuation)
+            },
+            Light::Spot(light) => light.sample(point),
+            Light::Directional(light) => light.sample(),
+        }
+    }
+}
+
+/// Sample a light located at a single position
+fn sample_position(point: DVec3, position: DVec3, intensity: Color, attenuation: Attenuation) -> LightSample {
+    let v = position - point;
+    let distance = v.length();
+    LightSample {
+        direction: v / distance,
+        distance,
+        intensity: intensity * attenuation.factor(distance),
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Light::Point(light)
+    }
+}
+
+impl From<AreaLight> for Light {
+    fn from(light: AreaLight) -> Self {
+        Light::Area(light)
+    }
+}
+
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Self {
+        Light::Spot(light)
+    }
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(light: DirectionalLight) -> Self {
+        Light::Directional(light)
+    }
+}
+
+/// A light without size, emitting the same intensity in every direction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub position: DVec3,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+}
+
+impl PointLight {
+    pub fn new(position: DVec3, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+            attenuation: Attenuation::default(),
+        }
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    pub fn sample(&self, point: DVec3) -> LightSample {
+        sample_position(point, self.position, self.intensity, self.attenuation)
+    }
+}
+
+/// A point light restricted to a cone.
+/// The intensity smoothly decreases between the inner and the outer angle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    pub position: DVec3,
+    /// Normalized axis of the cone
+    pub direction: DVec3,
+    /// Half angle of the fully lit cone, in radians
+    pub inner_angle: f64,
+    /// Half angle beyond which nothing is lit, in radians
+    pub outer_angle: f64,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+}
+
+impl SpotLight {
+    pub fn new(position: DVec3, direction: DVec3, inner_angle: f64, outer_angle: f64, intensity: Color) -> Self {
+        assert!(inner_angle <= outer_angle, "the inner angle of a spot light must not exceed the outer angle");
+        Self {
+            position,
+            direction: direction.normalize(),
+            inner_angle,
+            outer_angle,
+            intensity,
+            attenuation: Attenuation::default(),
+        }
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    /// Fraction of the intensity emitted toward a direction leaving the light
+    pub fn falloff(&self, direction: DVec3) -> f64 {
+        let cos_angle = direction.dot(self.direction);
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+        if cos_angle >= cos_inner {
+            1.0
+        } else if cos_angle <= cos_outer {
+            0.0
+        } else {
+            // Smoothstep between both cones
+            let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+            t * t * (3.0 - 2.0 * t)
+        }
+    }
+
+    pub fn sample(&self, point: DVec3) -> LightSample {
+        let mut sample = sample_position(point, self.position, self.intensity, self.attenuation);
+        sample.intensity *= self.falloff(-sample.direction);
+        sample
+    }
+}
+
+/// A light infinitely far away, like the sun, whose rays are all parallel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// Normalized direction the light travels in
+    pub direction: DVec3,
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: DVec3, intensity: Color) -> Self {
+        Self {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+
+    pub fn sample(&self) -> LightSample {
+        LightSample {
+            direction: -self.direction,
+            distance: f64::INFINITY,
+            intensity: self.intensity,
+        }
+    }
+}
+
+/// A rectangular light defined by a corner and two edges, divided in cells.
+/// Each cell is sampled to compute soft shadows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaLight {
+    pub corner: DVec3,
+    /// Size of a cell along the first edge
+    pub uvec: DVec3,
+    pub usteps: u32,
+    /// Size of a cell along the second edge
+    pub vvec: DVec3,
+    pub vsteps: u32,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+    /// Randomize the sample position inside each cell, otherwise the center is used
+    pub jitter: bool,
+}
+
+impl AreaLight {
+    pub fn new(corner: DVec3, full_uvec: DVec3, usteps: u32, full_vvec: DVec3, vsteps: u32, intensity: Color) -> Self {
+        assert!(usteps > 0 && vsteps > 0, "an area light needs at least one cell per edge");
+        Self {
+            corner,
+            uvec: full_uvec / usteps as f64,
+            usteps,
+            vvec: full_vvec / vsteps as f64,
+            vsteps,
+            intensity,
+            attenuation: Attenuation::default(),
+            jitter: true,
+        }
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    /// Number of cells of the light
+    pub fn samples(&self) -> u32 {
+        self.usteps * self.vsteps
+    }
+
+    /// Center of the light
+    pub fn position(&self) -> DVec3 {
+        self.corner + (self.uvec * self.usteps as f64 + self.vvec * self.vsteps as f64) / 2.0
+    }
+
+    /// Position inside the cell (u, v), offsets being in [0, 1[ across the cell
+    pub fn point_on_light(&self, u: u32, v: u32, offset_u: f64, offset_v: f64) -> DVec3 {
+        self.corner
+            + self.uvec * (u as f64 + offset_u)
+            + self.vvec * (v as f64 + offset_v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use super::*;
+
+    #[test]
+    fn creating_an_area_light() {
+        let light = AreaLight::new(
+            dvec3(0.0, 0.0, 0.0),
+            dvec3(2.0, 0.0, 0.0), 4,
+            dvec3(0.0, 0.0, 1.0), 2,
+            Color::WHITE
+        );
+        assert_eq!(light.corner, dvec3(0.0, 0.0, 0.0));
+        assert_eq!(light.uvec, dvec3(0.5, 0.0, 0.0));
+        assert_eq!(light.usteps, 4);
+        assert_eq!(light.vvec, dvec3(0.0, 0.0, 0.5));
+        assert_eq!(light.vsteps, 2);
+        assert_eq!(light.samples(), 8);
+        assert_eq!(light.position(), dvec3(1.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn finding_a_single_point_on_an_area_light() {
+        let light = AreaLight::new(
+            dvec3(0.0, 0.0, 0.0),
+            dvec3(2.0, 0.0, 0.0), 4,
+            dvec3(0.0, 0.0, 1.0), 2,
+            Color::WHITE
+        );
+        let examples = [
+            (0, 0, dvec3(0.25, 0.0, 0.25)),
+            (1, 0, dvec3(0.75, 0.0, 0.25)),
+            (0, 1, dvec3(0.25, 0.0, 0.75)),
+            (2, 0, dvec3(1.25, 0.0, 0.25)),
+            (3, 1, dvec3(1.75, 0.0, 0.75)),
+        ];
+        for (u, v, expected) in examples {
+            assert_eq!(light.point_on_light(u, v, 0.5, 0.5), expected);
+        }
+    }
+
+    #[test]
+    fn sampling_a_point_light() {
+        let light = Light::from(PointLight::new(dvec3(0.0, 4.0, 0.0), Color::WHITE));
+        let sample = light.sample(dvec3(0.0, 0.0, 0.0), 0, DVec2::splat(0.5));
+        assert_eq!(sample.direction, dvec3(0.0, 1.0, 0.0));
+        assert_eq!(sample.distance, 4.0);
+        assert_eq!(sample.intensity, Color::WHITE);
+    }
+
+    #[test]
+    fn inverse_square_attenuation_divides_the_intensity() {
+        let light = Light::from(
+            PointLight::new(dvec3(0.0, 4.0, 0.0), Color::WHITE)
+            .with_attenuation(Attenuation::InverseSquare)
+        );
+        let sample = light.sample(dvec3(0.0, 0.0, 0.0), 0, DVec2::splat(0.5));
+        assert_eq!(sample.intensity, Color::new(0.0625, 0.0625, 0.0625));
+    }
+
+    #[test]
+    fn sampling_the_cells_of_an_area_light() {
+        let light = Light::from(AreaLight::new(
+            dvec3(0.0, 0.0, 0.0),
+            dvec3(2.0, 0.0, 0.0), 4,
+            dvec3(0.0, 0.0, 1.0), 2,
+            Color::WHITE
+        ));
+        assert_eq!(light.sample_count(), 8);
+        // Cell (3, 1) is at (1.75, 0, 0.75)
+        let sample = light.sample(dvec3(1.75, -2.0, 0.75), 7, DVec2::splat(0.5));
+        assert!(sample.direction.abs_diff_eq(dvec3(0.0, 1.0, 0.0), 1.0e-9));
+        assert!((sample.distance - 2.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn a_spot_light_is_fully_lit_inside_the_inner_cone() {
+        let light = SpotLight::new(
+            dvec3(0.0, 10.0, 0.0),
+            dvec3(0.0, -1.0, 0.0),
+            std::f64::consts::FRAC_PI_8,
+            std::f64::consts::FRAC_PI_4,
+            Color::WHITE
+        );
+        let sample = light.sample(dvec3(0.5, 0.0, 0.0));
+        assert_eq!(sample.intensity, Color::WHITE);
+    }
+
+    #[test]
+    fn a_spot_light_does_not_light_outside_the_outer_cone() {
+        let light = SpotLight::new(
+            dvec3(0.0, 10.0, 0.0),
+            dvec3(0.0, -1.0, 0.0),
+            std::f64::consts::FRAC_PI_8,
+            std::f64::consts::FRAC_PI_4,
+            Color::WHITE
+        );
+        let sample = light.sample(dvec3(20.0, 0.0, 0.0));
+        assert_eq!(sample.intensity, Color::BLACK);
+    }
+
+    #[test]
+    fn a_spot_light_smoothly_falls_off_between_the_cones() {
+        let light = SpotLight::new(
+            dvec3(0.0, 0.0, 0.0),
+            dvec3(0.0, -1.0, 0.0),
+            0.0,
+            std::f64::consts::FRAC_PI_2,
+            Color::WHITE
+        );
+        // cos(60°) = 0.5 is halfway between cos(90°) and cos(0°)
+        let direction = dvec3(3.0_f64.sqrt() / 2.0, -0.5, 0.0);
+        assert!((light.falloff(direction) - 0.5).abs() < 1.0e-9);
+        let closer = dvec3(0.5, -(3.0_f64.sqrt()) / 2.0, 0.0);
+        let further = dvec3(0.9, -(0.19_f64.sqrt()), 0.0);
+        assert!(light.falloff(closer) > light.falloff(direction));
+        assert!(light.falloff(further) < light.falloff(direction));
+    }
+
+    #[test]
+    fn sampling_a_directional_light() {
+        let light = Light::from(DirectionalLight::new(dvec3(0.0, -2.0, 0.0), Color::WHITE));
+        let sample = light.sample(dvec3(5.0, 3.0, -2.0), 0, DVec2::splat(0.5));
+        assert_eq!(sample.direction, dvec3(0.0, 1.0, 0.0));
+        assert_eq!(sample.distance, f64::INFINITY);
+        assert_eq!(sample.intensity, Color::WHITE);
+    }
+}
@@ -1,4 +1,164 @@
-#[derive(Debug, Default, PartialEq)]
+use crate::common::{color::Color, BumpMap, Pattern};
+
+/// Surface properties used by the Phong reflection model,
+/// or by a physically based model when `pbr` is set
+#[derive(Debug, Clone, PartialEq)]
 pub struct Material {
+    pub color: Color,
+    /// Replaces the flat color when set
+    pub pattern: Option<Pattern>,
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    pub shininess: f64,
+    /// 0 for a fully diffuse surface, 1 for a perfect mirror
+    pub reflective: f64,
+    /// 0 for an opaque surface, 1 for a fully transparent one
+    pub transparency: f64,
+    pub refractive_index: f64,
+    /// Replaces the Phong diffuse and specular terms when set
+    pub pbr: Option<PbrMaterial>,
+    /// Color of the light given off by the surface, black for a surface that does not glow
+    pub emission: Color,
+    /// Multiplier applied to the emission color, can go above 1 for bright sources
+    pub emission_strength: f64,
+    /// Perturbs the shading normal when set
+    pub bump: Option<BumpMap>,
+}
+
+/// Metallic-roughness parameters, as authored in glTF pipelines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PbrMaterial {
+    /// Albedo of dielectrics, reflectance of metals.
+    /// Replaced by the material pattern when there is one.
+    pub base_color: Color,
+    /// 0 for a dielectric, 1 for a metal
+    pub metallic: f64,
+    /// 0 for a perfectly smooth surface, 1 for a fully rough one
+    pub roughness: f64,
+    /// Reflectance of dielectrics at normal incidence, 0.5 maps to the common 4%
+    pub specular: f64,
+}
+
+impl Default for PbrMaterial {
+    fn default() -> Self {
+        Self {
+            base_color: Color::WHITE,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+        }
+    }
+}
+
+impl PbrMaterial {
+    pub fn new(base_color: Color, metallic: f64, roughness: f64) -> Self {
+        Self {
+            base_color,
+            metallic,
+            roughness,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_specular(mut self, specular: f64) -> Self {
+        self.specular = specular;
+        self
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            pattern: None,
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            pbr: None,
+            emission: Color::BLACK,
+            emission_strength: 1.0,
+            bump: None,
+        }
+    }
+}
+
+impl Material {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    pub fn with_ambient(mut self, ambient: f64) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    pub fn with_diffuse(mut self, diffuse: f64) -> Self {
+        self.diffuse = diffuse;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: f64) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn with_shininess(mut self, shininess: f64) -> Self {
+        self.shininess = shininess;
+        self
+    }
+
+    pub fn with_reflective(mut self, reflective: f64) -> Self {
+        self.reflective = reflective;
+        self
+    }
+
+    pub fn with_transparency(mut self, transparency: f64) -> Self {
+        self.transparency = transparency;
+        self
+    }
+
+    pub fn with_refractive_index(mut self, refractive_index: f64) -> Self {
+        self.refractive_index = refractive_index;
+        self
+    }
+
+    pub fn with_pbr(mut self, pbr: PbrMaterial) -> Self {
+        self.pbr = Some(pbr);
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Color, strength: f64) -> Self {
+        self.emission = emission;
+        self.emission_strength = strength;
+        self
+    }
+
+    pub fn with_bump(mut self, bump: BumpMap) -> Self {
+        self.bump = Some(bump);
+        self
+    }
+
+    /// Radiance given off by the surface
+    pub fn emitted(&self) -> Color {
+        self.emission * self.emission_strength
+    }
 
-}
\ No newline at end of file
+    pub fn is_emissive(&self) -> bool {
+        self.emission_strength > 0.0 && self.emission != Color::BLACK
+    }
+}
@@ -0,0 +1,32 @@
+use crate::common::color::Color;
+
+/// A homogeneous participating medium, like fog or smoke, scattering light isotropically
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Medium {
+    /// Probability per unit of distance for a ray to interact with the medium
+    density: f64,
+    /// Fraction of the light scattered at each interaction, the rest being absorbed
+    albedo: Color,
+}
+
+impl Medium {
+    pub fn new(density: f64) -> Self {
+        Self {
+            density,
+            albedo: Color::WHITE,
+        }
+    }
+
+    pub fn with_albedo(mut self, albedo: Color) -> Self {
+        self.albedo = albedo;
+        self
+    }
+
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    pub fn albedo(&self) -> Color {
+        self.albedo
+    }
+}
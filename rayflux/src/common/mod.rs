@@ -8,6 +8,18 @@ pub mod object;
 pub mod shapes;
 pub mod transform;
 pub mod material;
+pub mod light;
+pub mod pattern;
+pub mod image_texture;
+pub mod random;
+pub mod environment;
+pub mod bump_map;
+pub mod medium;
+pub mod noise;
+pub mod obj;
+pub mod bounding_box;
+pub mod bvh;
+pub mod sdf;
 
 pub use gpu_context::*;
 pub use scene::*;
@@ -16,4 +28,16 @@ pub use inputs::*;
 pub use time::*;
 pub use object::*;
 pub use transform::*;
-pub use material::*;
\ No newline at end of file
+pub use material::*;
+pub use light::*;
+pub use pattern::*;
+pub use image_texture::*;
+pub use random::*;
+pub use environment::*;
+pub use bump_map::*;
+pub use medium::*;
+pub use noise::*;
+pub use obj::*;
+pub use bounding_box::*;
+pub use bvh::*;
+pub use sdf::*;
\ No newline at end of file
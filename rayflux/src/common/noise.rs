@@ -0,0 +1,33 @@
+/// A coherent noise function, used to build organic looking patterns
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Noise {
+    /// Smooth gradient noise, with features about one unit wide
+    Perlin,
+    /// Fractional Brownian motion, a sum of Perlin noise octaves of increasing frequency and decreasing amplitude.
+    /// `lacunarity` is the frequency ratio and `gain` the amplitude ratio between two octaves.
+    Fbm {
+        octaves: u32,
+        lacunarity: f64,
+        gain: f64,
+    },
+    /// Like fBm but summing the absolute value of each octave, giving creases where the noise crosses zero
+    Turbulence {
+        octaves: u32,
+        lacunarity: f64,
+        gain: f64,
+    },
+    /// Cellular noise, the distance to the nearest of random points scattered one per unit cube
+    Worley,
+}
+
+impl Noise {
+    /// fBm with the usual parameters, doubling the frequency and halving the amplitude at each octave
+    pub fn fbm(octaves: u32) -> Self {
+        Self::Fbm { octaves, lacunarity: 2.0, gain: 0.5 }
+    }
+
+    /// Turbulence with the usual parameters, doubling the frequency and halving the amplitude at each octave
+    pub fn turbulence(octaves: u32) -> Self {
+        Self::Turbulence { octaves, lacunarity: 2.0, gain: 0.5 }
+    }
+}
@@ -0,0 +1,393 @@
+use std::{fmt, path::Path};
+
+use glam::{DVec2, DVec3};
+
+use crate::common::{shapes::{Shape, SmoothTriangle, Triangle}, Material, Object};
+
+/// A line of an OBJ file that was skipped, either unsupported or malformed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjWarning {
+    /// 1 based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ObjWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Triangles sharing a name in an OBJ file
+#[derive(Debug, PartialEq)]
+pub struct ObjGroup {
+    name: String,
+    shapes: Vec<Shape>,
+}
+
+impl ObjGroup {
+    /// Name given by the `g` or `o` statement, empty for the faces declared before any group
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn shapes(&self) -> &Vec<Shape> {
+        &self.shapes
+    }
+
+    /// Turn every triangle into an object sharing the same material
+    pub fn into_objects(self, material: &Material) -> Vec<Object> {
+        self.shapes
+            .into_iter()
+            .map(|shape| Object::new(shape).with_material(material.clone()))
+            .collect()
+    }
+}
+
+/// The content of a Wavefront OBJ file.
+/// Polygons are split into triangles, smooth ones when every vertex of a face has a normal.
+#[derive(Debug, Default, PartialEq)]
+pub struct ObjFile {
+    vertices: Vec<DVec3>,
+    normals: Vec<DVec3>,
+    texture_coords: Vec<DVec2>,
+    groups: Vec<ObjGroup>,
+    warnings: Vec<ObjWarning>,
+}
+
+/// Indices of the vertex, texture coordinates and normal of a face corner
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+impl ObjFile {
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Parse the content of an OBJ file, lines that can't be used are reported in `warnings`
+    pub fn parse(source: &str) -> Self {
+        let mut file = Self::default();
+        let mut group = String::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let mut tokens = line.split_whitespace();
+            let Some(keyword) = tokens.next() else {
+                continue;
+            };
+            let arguments: Vec<&str> = tokens.collect();
+
+            let result = match keyword {
+                comment if comment.starts_with('#') => Ok(()),
+                "v" => parse_floats::<3>(&arguments)
+                    .map(|[x, y, z]| file.vertices.push(DVec3::new(x, y, z))),
+                "vn" => parse_floats::<3>(&arguments)
+                    .map(|[x, y, z]| file.normals.push(DVec3::new(x, y, z))),
+                "vt" => parse_floats::<1>(&arguments).and_then(|[u]| {
+                    let v = match arguments.get(1) {
+                        Some(v) => parse_float(v)?,
+                        None => 0.0,
+                    };
+                    file.texture_coords.push(DVec2::new(u, v));
+                    Ok(())
+                }),
+                "f" => file.add_face(&group, &arguments),
+                "g" | "o" => {
+                    group = arguments.join(" ");
+                    Ok(())
+                },
+                _ => Err(format!("unsupported statement `{}`", keyword)),
+            };
+
+            if let Err(message) = result {
+                file.warnings.push(ObjWarning { line: index + 1, message });
+            }
+        }
+
+        file
+    }
+
+    pub fn vertices(&self) -> &Vec<DVec3> {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &Vec<DVec3> {
+        &self.normals
+    }
+
+    pub fn texture_coords(&self) -> &Vec<DVec2> {
+        &self.texture_coords
+    }
+
+    pub fn groups(&self) -> &Vec<ObjGroup> {
+        &self.groups
+    }
+
+    pub fn warnings(&self) -> &Vec<ObjWarning> {
+        &self.warnings
+    }
+
+    pub fn group(&self, name: &str) -> Option<&ObjGroup> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    pub fn into_groups(self) -> Vec<ObjGroup> {
+        self.groups
+    }
+
+    /// Every triangle of every group as objects sharing the same material
+    pub fn into_objects(self, material: &Material) -> Vec<Object> {
+        self.groups
+            .into_iter()
+            .flat_map(|group| group.into_objects(material))
+            .collect()
+    }
+
+    /// A group object holding one group object per named group of the file
+    pub fn into_group(self, material: &Material) -> Object {
+        Object::new_group(
+            self.groups
+                .into_iter()
+                .map(|group| Object::new_group(group.into_objects(material)))
+                .collect()
+        )
+    }
+
+    fn add_face(&mut self, group: &str, arguments: &[&str]) -> Result<(), String> {
+        if arguments.len() < 3 {
+            return Err(format!("a face needs at least 3 vertices, found {}", arguments.len()));
+        }
+
+        let corners = arguments
+            .iter()
+            .map(|argument| self.parse_face_vertex(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Fan triangulation, the polygons are expected to be convex
+        let triangles: Vec<Shape> = (1..corners.len() - 1)
+            .map(|i| self.triangle([corners[0], corners[i], corners[i + 1]]))
+            .collect();
+
+        match self.groups.iter_mut().find(|g| g.name == group) {
+            Some(existing) => existing.shapes.extend(triangles),
+            None => self.groups.push(ObjGroup { name: group.to_string(), shapes: triangles }),
+        }
+        Ok(())
+    }
+
+    fn triangle(&self, corners: [FaceVertex; 3]) -> Shape {
+        let [p1, p2, p3] = corners.map(|(v, _, _)| self.vertices[v]);
+        let uvs = corners.map(|(_, vt, _)| vt.map(|vt| self.texture_coords[vt]));
+        let normals = corners.map(|(_, _, vn)| vn.map(|vn| self.normals[vn]));
+
+        let uvs = match uvs {
+            [Some(t1), Some(t2), Some(t3)] => Some((t1, t2, t3)),
+            _ => None,
+        };
+
+        match normals {
+            [Some(n1), Some(n2), Some(n3)] => {
+                let mut triangle = SmoothTriangle::new(p1, p2, p3, n1, n2, n3);
+                if let Some((t1, t2, t3)) = uvs {
+                    triangle = triangle.with_texture_coords(t1, t2, t3);
+                }
+                Shape::SmoothTriangle(triangle)
+            },
+            _ => {
+                let mut triangle = Triangle::new(p1, p2, p3);
+                if let Some((t1, t2, t3)) = uvs {
+                    triangle = triangle.with_texture_coords(t1, t2, t3);
+                }
+                Shape::Triangle(triangle)
+            },
+        }
+    }
+
+    /// Parse `v`, `v/vt`, `v//vn` or `v/vt/vn` into 0 based indices
+    fn parse_face_vertex(&self, argument: &str) -> Result<FaceVertex, String> {
+        let mut parts = argument.split('/');
+        let vertex = parts.next().unwrap_or_default();
+        let texture = parts.next().filter(|part| !part.is_empty());
+        let normal = parts.next().filter(|part| !part.is_empty());
+
+        let vertex = resolve_index(vertex, self.vertices.len(), "vertex")?;
+        let texture = texture
+            .map(|index| resolve_index(index, self.texture_coords.len(), "texture coordinates"))
+            .transpose()?;
+        let normal = normal
+            .map(|index| resolve_index(index, self.normals.len(), "normal"))
+            .transpose()?;
+
+        Ok((vertex, texture, normal))
+    }
+}
+
+/// Convert a 1 based index, or a negative one relative to the end, into a 0 based one
+fn resolve_index(token: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let index: i64 = token.parse().map_err(|_| format!("invalid {} index `{}`", kind, token))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{} index {} is out of range", kind, index));
+    }
+    Ok(resolved as usize)
+}
+
+fn parse_float(token: &str) -> Result<f64, String> {
+    token.parse().map_err(|_| format!("invalid number `{}`", token))
+}
+
+fn parse_floats<const N: usize>(arguments: &[&str]) -> Result<[f64; N], String> {
+    if arguments.len() < N {
+        return Err(format!("expected {} numbers, found {}", N, arguments.len()));
+    }
+    let mut values = [0.0; N];
+    for (value, token) in values.iter_mut().zip(arguments) {
+        *value = parse_float(token)?;
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(shape: &Shape) -> &Triangle {
+        match shape {
+            Shape::Triangle(triangle) => triangle,
+            Shape::SmoothTriangle(triangle) => triangle.triangle(),
+            _ => panic!("expected a triangle"),
+        }
+    }
+
+    #[test]
+    fn ignoring_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+
+        let file = ObjFile::parse(gibberish);
+
+        assert_eq!(file.warnings().len(), 5);
+        assert_eq!(file.warnings()[0].line, 1);
+        assert!(file.groups().is_empty());
+    }
+
+    #[test]
+    fn vertex_records() {
+        let file = ObjFile::parse("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0");
+
+        assert_eq!(file.vertices()[0], DVec3::new(-1.0, 1.0, 0.0));
+        assert_eq!(file.vertices()[1], DVec3::new(-1.0, 0.5, 0.0));
+        assert_eq!(file.vertices()[2], DVec3::new(1.0, 0.0, 0.0));
+        assert_eq!(file.vertices()[3], DVec3::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn parsing_triangle_faces() {
+        let file = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4");
+
+        let shapes = file.group("").unwrap().shapes();
+        let t1 = triangle(&shapes[0]);
+        let t2 = triangle(&shapes[1]);
+        assert_eq!(t1.p1(), file.vertices()[0]);
+        assert_eq!(t1.p2(), file.vertices()[1]);
+        assert_eq!(t1.p3(), file.vertices()[2]);
+        assert_eq!(t2.p1(), file.vertices()[0]);
+        assert_eq!(t2.p2(), file.vertices()[2]);
+        assert_eq!(t2.p3(), file.vertices()[3]);
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let file = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5");
+
+        let shapes = file.group("").unwrap().shapes();
+        assert_eq!(shapes.len(), 3);
+        let t3 = triangle(&shapes[2]);
+        assert_eq!(t3.p1(), file.vertices()[0]);
+        assert_eq!(t3.p2(), file.vertices()[3]);
+        assert_eq!(t3.p3(), file.vertices()[4]);
+    }
+
+    #[test]
+    fn triangles_in_groups() {
+        let file = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\ng FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4");
+
+        let first = triangle(&file.group("FirstGroup").unwrap().shapes()[0]);
+        let second = triangle(&file.group("SecondGroup").unwrap().shapes()[0]);
+        assert_eq!(first.p3(), file.vertices()[2]);
+        assert_eq!(second.p3(), file.vertices()[3]);
+        assert!(file.group("").is_none());
+    }
+
+    #[test]
+    fn vertex_normal_records() {
+        let file = ObjFile::parse("vn 0 0 1\nvn 0.707 0 -0.707\nvn 1 2 3");
+
+        assert_eq!(file.normals()[0], DVec3::new(0.0, 0.0, 1.0));
+        assert_eq!(file.normals()[1], DVec3::new(0.707, 0.0, -0.707));
+        assert_eq!(file.normals()[2], DVec3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn faces_with_normals() {
+        let file = ObjFile::parse("v 0 1 0\nv -1 0 0\nv 1 0 0\nvn -1 0 0\nvn 1 0 0\nvn 0 1 0\nf 1//3 2//1 3//2\nf 1/0/3 2/102/1 3/14/2");
+
+        let shapes = file.group("").unwrap().shapes();
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(file.warnings().len(), 1);
+        let Shape::SmoothTriangle(t1) = &shapes[0] else { panic!("expected a smooth triangle") };
+        assert_eq!(t1.triangle().p1(), file.vertices()[0]);
+        assert_eq!(t1.n1(), file.normals()[2]);
+        assert_eq!(t1.n2(), file.normals()[0]);
+        assert_eq!(t1.n3(), file.normals()[1]);
+    }
+
+    #[test]
+    fn faces_with_texture_coordinates() {
+        let file = ObjFile::parse("v 0 1 0\nv -1 0 0\nv 1 0 0\nvt 0.5 1\nvt 0 0\nvt 1\nf 1/1 2/2 3/3");
+
+        let t = triangle(&file.group("").unwrap().shapes()[0]);
+        let expected = [DVec2::new(0.5, 1.0), DVec2::new(0.0, 0.0), DVec2::new(1.0, 0.0)];
+        assert_eq!(t.texture_coords(), Some(&expected));
+    }
+
+    #[test]
+    fn negative_indices_are_relative_to_the_last_vertex() {
+        let file = ObjFile::parse("v 0 1 0\nv -1 0 0\nv 1 0 0\nf -3 -2 -1");
+
+        let t = triangle(&file.group("").unwrap().shapes()[0]);
+        assert_eq!(t.p1(), file.vertices()[0]);
+        assert_eq!(t.p3(), file.vertices()[2]);
+    }
+
+    #[test]
+    fn malformed_lines_are_reported() {
+        let file = ObjFile::parse("v 0 1\nv 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 4\nf 1 2\nusemtl red");
+
+        let lines: Vec<usize> = file.warnings().iter().map(|w| w.line).collect();
+        assert_eq!(lines, vec![1, 5, 6, 7]);
+        assert!(file.groups().is_empty());
+    }
+
+    #[test]
+    fn converting_a_file_to_objects() {
+        let file = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\ng a\nf 1 2 3\ng b\nf 1 2 3 4");
+        let material = Material::default().with_color(crate::common::color::Color::RED);
+
+        let objects = file.into_objects(&material);
+
+        assert_eq!(objects.len(), 3);
+        assert!(objects.iter().all(|o| o.material().color == crate::common::color::Color::RED));
+    }
+
+    #[test]
+    fn converting_a_file_to_a_group() {
+        let file = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\ng FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4");
+
+        let group = file.into_group(&Material::default());
+
+        assert_eq!(group.children().len(), 2);
+        assert_eq!(group.children()[1].children().len(), 1);
+    }
+}
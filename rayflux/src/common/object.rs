@@ -1,5 +1,7 @@
+use glam::{DMat4, DVec3};
+
 use crate::{
-    common::{shapes::{Cube, Cylinder, Plane, Shape, Sphere}, Material, Transform}
+    common::{shapes::{Cone, Csg, CsgOperation, Cube, Cylinder, Disk, Group, Heightfield, Plane, Quad, SdfShape, Shape, SmoothTriangle, Sphere, Torus, Triangle}, Material, Medium, Sdf, Transform}
 };
 
 #[derive(Debug, PartialEq)]
@@ -7,6 +9,14 @@ pub struct Object {
     shape: Shape,
     transform: Transform,
     material: Material,
+    shadow: bool,
+    /// Turns the object into the boundary of a volume filled with the medium
+    medium: Option<Medium>,
+    /// Conversion from world space to the space of the parent group, identity for a top level object
+    parent_inverse_matrix: DMat4,
+    /// Cached conversions from world space to object space, updated with the transforms
+    world_inverse_matrix: DMat4,
+    world_inverse_transpose_matrix: DMat4,
 }
 
 impl Object {
@@ -15,44 +25,103 @@ impl Object {
             shape,
             transform: Transform::default(),
             material: Material::default(),
+            shadow: true,
+            medium: None,
+            parent_inverse_matrix: DMat4::IDENTITY,
+            world_inverse_matrix: DMat4::IDENTITY,
+            world_inverse_transpose_matrix: DMat4::IDENTITY,
         }
     }
 
     pub fn new_sphere() -> Self {
-        Self {
-            shape: Shape::Sphere(Sphere::default()),
-            transform: Transform::default(),
-            material: Material::default(),
-        }
+        Self::new(Shape::Sphere(Sphere::default()))
     }
 
     pub fn new_plane() -> Self {
-        Self {
-            shape: Shape::Plane(Plane::default()),
-            transform: Transform::default(),
-            material: Material::default(),
-        }
+        Self::new(Shape::Plane(Plane::default()))
     }
 
     pub fn new_cube() -> Self {
-        Self {
-            shape: Shape::Cube(Cube::default()),
-            transform: Transform::default(),
-            material: Material::default(),
-        }
+        Self::new(Shape::Cube(Cube::default()))
     }
 
     pub fn new_cylinder() -> Self {
-        Self {
-            shape: Shape::Cylinder(Cylinder::default()),
-            transform: Transform::default(),
-            material: Material::default(),
+        Self::new(Shape::Cylinder(Cylinder::default()))
+    }
+
+    pub fn new_cone() -> Self {
+        Self::new(Shape::Cone(Cone::default()))
+    }
+
+    pub fn new_torus(major_radius: f64, minor_radius: f64) -> Self {
+        Self::new(Shape::Torus(Torus::new(major_radius, minor_radius)))
+    }
+
+    pub fn new_disk(radius: f64) -> Self {
+        Self::new(Shape::Disk(Disk::new(radius)))
+    }
+
+    pub fn new_quad(corner: DVec3, edge1: DVec3, edge2: DVec3) -> Self {
+        Self::new(Shape::Quad(Quad::new(corner, edge1, edge2)))
+    }
+
+    pub fn new_sdf(sdf: Sdf) -> Self {
+        Self::new(Shape::Sdf(SdfShape::new(sdf)))
+    }
+
+    pub fn new_heightfield(heightfield: Heightfield) -> Self {
+        Self::new(Shape::Heightfield(heightfield))
+    }
+
+    pub fn new_triangle(p1: DVec3, p2: DVec3, p3: DVec3) -> Self {
+        Self::new(Shape::Triangle(Triangle::new(p1, p2, p3)))
+    }
+
+    pub fn new_smooth_triangle(p1: DVec3, p2: DVec3, p3: DVec3, n1: DVec3, n2: DVec3, n3: DVec3) -> Self {
+        Self::new(Shape::SmoothTriangle(SmoothTriangle::new(p1, p2, p3, n1, n2, n3)))
+    }
+
+    pub fn new_group(children: Vec<Object>) -> Self {
+        let mut group = Self::new(Shape::Group(Group::new()));
+        for child in children {
+            group.add_child(child);
         }
+        group
+    }
+
+    /// Combine two objects with a boolean operation
+    pub fn new_csg(operation: CsgOperation, left: Object, right: Object) -> Self {
+        let mut csg = Self::new(Shape::Csg(Csg::new(operation, left, right)));
+        csg.update_matrix();
+        csg
     }
 
     pub fn with_transform(mut self, transform: Transform) -> Self {
-        self.transform = transform;
-        self.transform.update_matrix();
+        self.set_transform(transform);
+        self
+    }
+
+    /// Add an object to a group, its transform becomes relative to the group
+    pub fn with_child(mut self, child: Object) -> Self {
+        self.add_child(child);
+        self
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    /// Enable or disable the shadows cast by this object
+    pub fn with_shadow(mut self, shadow: bool) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    /// Fill the inside of the object with a medium, its surface is then no longer rendered.
+    /// The object must be closed for the inside to be well defined.
+    pub fn with_medium(mut self, medium: Medium) -> Self {
+        self.medium = Some(medium);
         self
     }
 
@@ -64,11 +133,92 @@ impl Object {
         &self.transform
     }
 
-    pub fn transform_mut(&mut self) -> &mut Transform {
-        &mut self.transform
+    /// Replace the transform, the matrices of the object and of its children are updated with it
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+        self.update_matrix();
     }
 
     pub fn material(&self) -> &Material {
         &self.material
     }
+
+    pub fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    pub fn shadow(&self) -> bool {
+        self.shadow
+    }
+
+    pub fn set_shadow(&mut self, shadow: bool) {
+        self.shadow = shadow;
+    }
+
+    /// Add an object to a group, its transform becomes relative to the group
+    pub fn add_child(&mut self, mut child: Object) {
+        let world_inverse_matrix = self.world_inverse_matrix();
+        let Shape::Group(group) = &mut self.shape else {
+            panic!("Only groups can have children");
+        };
+        child.set_parent_inverse_matrix(world_inverse_matrix);
+        group.children_mut().push(child);
+    }
+
+    /// Children of a group or operands of a csg, empty for the other shapes
+    pub fn children(&self) -> &[Object] {
+        match &self.shape {
+            Shape::Group(group) => group.children(),
+            Shape::Csg(csg) => csg.operands(),
+            _ => &[],
+        }
+    }
+
+    /// Check if an object is this one or one of its descendants
+    pub fn includes(&self, other: &Object) -> bool {
+        std::ptr::eq(self, other) || self.children().iter().any(|child| child.includes(other))
+    }
+
+    /// Update the transform matrices, and the ones composed with them in the children of a group
+    pub fn update_matrix(&mut self) {
+        self.transform.update_matrix();
+        self.set_parent_inverse_matrix(self.parent_inverse_matrix);
+    }
+
+    /// Conversion from world space to object space, through every parent group
+    pub fn world_inverse_matrix(&self) -> DMat4 {
+        self.world_inverse_matrix
+    }
+
+    /// Conversion of the normals from object space to world space, through every parent group
+    pub fn world_inverse_transpose_matrix(&self) -> DMat4 {
+        self.world_inverse_transpose_matrix
+    }
+
+    fn set_parent_inverse_matrix(&mut self, parent_inverse_matrix: DMat4) {
+        self.parent_inverse_matrix = parent_inverse_matrix;
+        let world_inverse_matrix = self.transform.inverse_matrix() * parent_inverse_matrix;
+        self.world_inverse_matrix = world_inverse_matrix;
+        self.world_inverse_transpose_matrix = if parent_inverse_matrix == DMat4::IDENTITY {
+            self.transform.inverse_transpose_matrix()
+        } else {
+            world_inverse_matrix.transpose()
+        };
+        let children: &mut [Object] = match &mut self.shape {
+            Shape::Group(group) => group.children_mut(),
+            Shape::Csg(csg) => csg.operands_mut(),
+            _ => &mut [],
+        };
+        for child in children {
+            child.set_parent_inverse_matrix(world_inverse_matrix);
+        }
+    }
+
+    pub fn medium(&self) -> Option<&Medium> {
+        self.medium.as_ref()
+    }
+
+    pub fn set_medium(&mut self, medium: Option<Medium>) {
+        self.medium = medium;
+    }
 }
\ No newline at end of file
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use crate::common::{color::Color, ImageTexture, Noise, Transform};
+
+/// A procedural color pattern that can be applied to a material instead of a flat color
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    kind: PatternKind,
+    transform: Transform,
+}
+
+/// The different pattern types.
+/// Patterns are composed of sub-patterns, which allows nesting patterns inside each other.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind {
+    /// A single color
+    Solid(Color),
+    /// Alternates between two patterns along the x axis
+    Stripe(Box<Pattern>, Box<Pattern>),
+    /// Linear interpolation between two patterns along the x axis
+    Gradient(Box<Pattern>, Box<Pattern>),
+    /// Alternates between two patterns in concentric rings around the y axis
+    Ring(Box<Pattern>, Box<Pattern>),
+    /// Alternates between two patterns in 3D cubes
+    Checker(Box<Pattern>, Box<Pattern>),
+    /// Mix of two patterns, the factor is the weight of the second one
+    Blend(Box<Pattern>, Box<Pattern>, f64),
+    /// An image wrapped around the object using a uv mapping
+    Texture(Arc<ImageTexture>, UvMapping),
+    /// Mix of two patterns, weighted by the value of a noise between 0 and 1
+    Noise(Box<Pattern>, Box<Pattern>, Noise),
+    /// A pattern whose input point is displaced by a noise, the factor being the largest displacement
+    Perturb(Box<Pattern>, Noise, f64),
+}
+
+/// How a point on a shape is converted to 2D texture coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UvMapping {
+    /// Latitude/longitude mapping around a unit sphere
+    Spherical,
+    /// Projection on the xz plane, tiling every unit when the texture repeats
+    Planar,
+    /// Mapping around the y axis of a unit cylinder, caps are mapped as disks
+    Cylindrical,
+    /// Six faces of a unit cube laid out in a horizontal cross
+    Cube,
+    /// Texture coordinates of the shape itself, like the ones of a quad or of a mesh loaded with its `vt` entries.
+    /// Away from any shape the planar mapping is used.
+    Object,
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind) -> Self {
+        Self {
+            kind,
+            transform: Transform::default(),
+        }
+    }
+
+    pub fn new_solid(color: Color) -> Self {
+        Self::new(PatternKind::Solid(color))
+    }
+
+    pub fn new_stripe(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(PatternKind::Stripe(Box::new(a.into()), Box::new(b.into())))
+    }
+
+    pub fn new_gradient(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(PatternKind::Gradient(Box::new(a.into()), Box::new(b.into())))
+    }
+
+    pub fn new_ring(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(PatternKind::Ring(Box::new(a.into()), Box::new(b.into())))
+    }
+
+    pub fn new_checker(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(PatternKind::Checker(Box::new(a.into()), Box::new(b.into())))
+    }
+
+    pub fn new_blend(a: impl Into<Pattern>, b: impl Into<Pattern>, factor: f64) -> Self {
+        Self::new(PatternKind::Blend(Box::new(a.into()), Box::new(b.into()), factor))
+    }
+
+    pub fn new_texture(texture: Arc<ImageTexture>, mapping: UvMapping) -> Self {
+        Self::new(PatternKind::Texture(texture, mapping))
+    }
+
+    pub fn new_noise(a: impl Into<Pattern>, b: impl Into<Pattern>, noise: Noise) -> Self {
+        Self::new(PatternKind::Noise(Box::new(a.into()), Box::new(b.into()), noise))
+    }
+
+    pub fn new_perturb(pattern: impl Into<Pattern>, noise: Noise, scale: f64) -> Self {
+        Self::new(PatternKind::Perturb(Box::new(pattern.into()), noise, scale))
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self.transform.update_matrix();
+        self
+    }
+
+    pub fn kind(&self) -> &PatternKind {
+        &self.kind
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+}
+
+impl From<Color> for Pattern {
+    fn from(color: Color) -> Self {
+        Self::new_solid(color)
+    }
+}
@@ -1,14 +1,31 @@
-use crate::common::{Camera, GpuContext, Object};
+use std::sync::OnceLock;
+
+use crate::common::{Bvh, Camera, EnvironmentMap, GpuContext, Light, Medium, Object};
 
 #[derive(Default)]
 pub struct Scene {
     camera: Camera,
     objects: Vec<Object>,
+    lights: Vec<Light>,
+    /// Seen by the rays missing every object, the camera background is used when there is none
+    environment: Option<EnvironmentMap>,
+    /// Fills the whole scene, like an atmospheric fog
+    medium: Option<Medium>,
+    /// Built from the objects on first use, and dropped whenever they may change
+    bvh: OnceLock<Bvh>,
+    /// Whether an object holds a medium, computed on first use like the bvh
+    objects_have_media: OnceLock<bool>,
 }
 
 impl Scene {
     pub fn add_object(&mut self, object: Object) {
         self.objects.push(object);
+        self.bvh.take();
+        self.objects_have_media.take();
+    }
+
+    pub fn add_light(&mut self, light: impl Into<Light>) {
+        self.lights.push(light.into());
     }
 
     pub fn camera(&self) -> &Camera {
@@ -19,11 +36,11 @@ impl Scene {
         &mut self.camera
     }
 
-    pub fn init(&mut self, context: &mut GpuContext) {
+    pub fn init(&mut self, _context: &mut GpuContext) {
 
     }
 
-    pub fn update(&mut self, context: &GpuContext) {
+    pub fn update(&mut self, _context: &GpuContext) {
 
     }
 
@@ -32,6 +49,50 @@ impl Scene {
     }
 
     pub fn objects_mut(&mut self) -> &mut Vec<Object> {
+        self.bvh.take();
+        self.objects_have_media.take();
         &mut self.objects
     }
+
+    /// Hierarchy of the objects bounds, rebuilt after the objects changed
+    pub fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::build(&self.objects))
+    }
+
+    pub fn lights(&self) -> &Vec<Light> {
+        &self.lights
+    }
+
+    pub fn lights_mut(&mut self) -> &mut Vec<Light> {
+        &mut self.lights
+    }
+
+    pub fn set_environment(&mut self, environment: Option<EnvironmentMap>) {
+        self.environment = environment;
+    }
+
+    pub fn environment(&self) -> Option<&EnvironmentMap> {
+        self.environment.as_ref()
+    }
+
+    pub fn environment_mut(&mut self) -> Option<&mut EnvironmentMap> {
+        self.environment.as_mut()
+    }
+
+    pub fn set_medium(&mut self, medium: Option<Medium>) {
+        self.medium = medium;
+    }
+
+    pub fn medium(&self) -> Option<&Medium> {
+        self.medium.as_ref()
+    }
+
+    /// Check if some rays can be scattered before reaching a surface
+    pub fn has_media(&self) -> bool {
+        self.medium.is_some() || *self.objects_have_media.get_or_init(|| self.objects.iter().any(has_medium))
+    }
+}
+
+fn has_medium(object: &Object) -> bool {
+    object.medium().is_some() || object.children().iter().any(has_medium)
 }
\ No newline at end of file
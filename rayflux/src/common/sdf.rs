@@ -0,0 +1,55 @@
+use glam::DVec3;
+
+/// A signed distance function, negative inside the surface, built from primitives and operations.
+/// Every primitive is centered on the origin, `translate` moves them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sdf {
+    Sphere { radius: f64 },
+    /// A box given its half size along each axis
+    Cuboid { half_extents: DVec3 },
+    /// A ring around the y axis
+    Torus { major_radius: f64, minor_radius: f64 },
+    /// A segment inflated by a radius
+    Capsule { a: DVec3, b: DVec3, radius: f64 },
+    Translate(Box<Sdf>, DVec3),
+    /// Union blending the surfaces over a distance `k`
+    SmoothUnion(Box<Sdf>, Box<Sdf>, f64),
+    /// The first function with the inside of the second one removed
+    Subtraction(Box<Sdf>, Box<Sdf>),
+    /// Infinite copies of the function every period along each axis, a null period disabling the repetition on that axis
+    Repeat(Box<Sdf>, DVec3),
+}
+
+impl Sdf {
+    pub fn sphere(radius: f64) -> Self {
+        Sdf::Sphere { radius }
+    }
+
+    pub fn cuboid(half_extents: DVec3) -> Self {
+        Sdf::Cuboid { half_extents }
+    }
+
+    pub fn torus(major_radius: f64, minor_radius: f64) -> Self {
+        Sdf::Torus { major_radius, minor_radius }
+    }
+
+    pub fn capsule(a: DVec3, b: DVec3, radius: f64) -> Self {
+        Sdf::Capsule { a, b, radius }
+    }
+
+    pub fn translate(self, offset: DVec3) -> Self {
+        Sdf::Translate(Box::new(self), offset)
+    }
+
+    pub fn smooth_union(self, other: Sdf, k: f64) -> Self {
+        Sdf::SmoothUnion(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn subtract(self, other: Sdf) -> Self {
+        Sdf::Subtraction(Box::new(self), Box::new(other))
+    }
+
+    pub fn repeat(self, period: DVec3) -> Self {
+        Sdf::Repeat(Box::new(self), period)
+    }
+}
@@ -1,9 +1,26 @@
+use std::{path::Path, sync::OnceLock};
+
+use glam::{DVec2, DVec3};
+use image::{error::{ParameterError, ParameterErrorKind}, ImageError};
+
+use crate::common::{BoundingBox, Bvh, Object, Sdf};
+
 #[derive(Debug, PartialEq)]
 pub enum Shape {
     Sphere(Sphere),
     Plane(Plane),
     Cube(Cube),
     Cylinder(Cylinder),
+    Cone(Cone),
+    Torus(Torus),
+    Disk(Disk),
+    Quad(Quad),
+    Sdf(SdfShape),
+    Heightfield(Heightfield),
+    Triangle(Triangle),
+    SmoothTriangle(SmoothTriangle),
+    Group(Group),
+    Csg(Csg),
 }
 
 #[derive(Debug, Default, PartialEq)]
@@ -26,4 +43,409 @@ impl Cylinder {
     pub fn new() -> Self {
         Self::default()
     }
+}
+
+/// A double-napped cone around the y axis, its radius being |y|
+///
+/// Unlike `Cylinder`, whose default is truncated at `min = max = 0`, the default cone is unbounded in both directions
+#[derive(Debug, PartialEq)]
+pub struct Cone {
+    pub min: f64,
+    pub max: f64,
+    pub closed: bool,
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self {
+            min: f64::NEG_INFINITY,
+            max: f64::INFINITY,
+            closed: false,
+        }
+    }
+}
+
+impl Cone {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// A ring around the y axis, made of a tube of radius `minor_radius` whose center is at `major_radius` from the axis
+#[derive(Debug, PartialEq)]
+pub struct Torus {
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl Default for Torus {
+    fn default() -> Self {
+        Self {
+            major_radius: 1.0,
+            minor_radius: 0.25,
+        }
+    }
+}
+
+impl Torus {
+    pub fn new(major_radius: f64, minor_radius: f64) -> Self {
+        Self {
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+/// A disk of the xz plane centered on the origin, facing +y, with an optional hole making it an annulus
+#[derive(Debug, PartialEq)]
+pub struct Disk {
+    pub radius: f64,
+    pub inner_radius: f64,
+}
+
+impl Default for Disk {
+    fn default() -> Self {
+        Self {
+            radius: 1.0,
+            inner_radius: 0.0,
+        }
+    }
+}
+
+impl Disk {
+    pub fn new(radius: f64) -> Self {
+        Self {
+            radius,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_inner_radius(mut self, inner_radius: f64) -> Self {
+        self.inner_radius = inner_radius;
+        self
+    }
+}
+
+/// A parallelogram starting at a corner and spanned by two edges, its normal is computed once like a triangle's
+#[derive(Debug, PartialEq)]
+pub struct Quad {
+    corner: DVec3,
+    edge1: DVec3,
+    edge2: DVec3,
+    normal: DVec3,
+}
+
+impl Quad {
+    pub fn new(corner: DVec3, edge1: DVec3, edge2: DVec3) -> Self {
+        Self {
+            corner,
+            edge1,
+            edge2,
+            normal: edge2.cross(edge1).normalize(),
+        }
+    }
+
+    pub fn corner(&self) -> DVec3 {
+        self.corner
+    }
+
+    pub fn edge1(&self) -> DVec3 {
+        self.edge1
+    }
+
+    pub fn edge2(&self) -> DVec3 {
+        self.edge2
+    }
+
+    pub fn normal(&self) -> DVec3 {
+        self.normal
+    }
+}
+
+/// A surface defined by a signed distance function, intersected by sphere tracing inside its bounds
+#[derive(Debug, PartialEq)]
+pub struct SdfShape {
+    sdf: Sdf,
+    bounds: BoundingBox,
+}
+
+impl SdfShape {
+    /// The bounds are computed from the function, they are infinite along the repeated axes
+    pub fn new(sdf: Sdf) -> Self {
+        let bounds = sdf.bounds();
+        Self {
+            sdf,
+            bounds,
+        }
+    }
+
+    /// Restrict the tracing to a box, required to render a repeated function
+    pub fn with_bounds(mut self, bounds: BoundingBox) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    pub fn sdf(&self) -> &Sdf {
+        &self.sdf
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+}
+
+/// A terrain over the unit square of the xz plane, made of a grid of heights in [0, 1].
+/// The sample (x, z) of a `width` by `depth` grid is at (x / (width - 1), z / (depth - 1)).
+#[derive(Debug, PartialEq)]
+pub struct Heightfield {
+    width: usize,
+    depth: usize,
+    /// Row major, one row per z
+    heights: Vec<f64>,
+    min_height: f64,
+    max_height: f64,
+}
+
+impl Heightfield {
+    pub fn new(width: usize, depth: usize, heights: Vec<f64>) -> Self {
+        assert!(width >= 2 && depth >= 2, "a heightfield needs at least 2 by 2 samples");
+        assert_eq!(heights.len(), width * depth, "height count does not match heightfield size");
+        let min_height = heights.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_height = heights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Self {
+            width,
+            depth,
+            heights,
+            min_height,
+            max_height,
+        }
+    }
+
+    /// Load the heights from a grayscale image, black being 0 and white 1.
+    /// The top row of the image is at z = 0, and the image must be at least 2x2.
+    pub fn load<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        let image = image::open(path)?.to_luma32f();
+        if image.width() < 2 || image.height() < 2 {
+            return Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(
+                format!("a heightfield needs at least 2x2 samples, the image is {}x{}", image.width(), image.height())
+            ))));
+        }
+        let heights = image.pixels().map(|p| p[0] as f64).collect();
+        Ok(Self::new(image.width() as usize, image.height() as usize, heights))
+    }
+
+    /// Number of samples along x
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of samples along z
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Height of a sample, the coordinates being clamped to the grid
+    pub fn height(&self, x: i64, z: i64) -> f64 {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let z = z.clamp(0, self.depth as i64 - 1) as usize;
+        self.heights[z * self.width + x]
+    }
+
+    pub fn min_height(&self) -> f64 {
+        self.min_height
+    }
+
+    pub fn max_height(&self) -> f64 {
+        self.max_height
+    }
+}
+
+/// A flat triangle, its edges and normal are computed once from the vertices
+#[derive(Debug, PartialEq)]
+pub struct Triangle {
+    p1: DVec3,
+    p2: DVec3,
+    p3: DVec3,
+    e1: DVec3,
+    e2: DVec3,
+    normal: DVec3,
+    /// Texture coordinates of the vertices, the barycentric coordinates are used when there are none
+    texture_coords: Option<[DVec2; 3]>,
+}
+
+impl Triangle {
+    pub fn new(p1: DVec3, p2: DVec3, p3: DVec3) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(e1).normalize(),
+            texture_coords: None,
+        }
+    }
+
+    pub fn with_texture_coords(mut self, t1: DVec2, t2: DVec2, t3: DVec2) -> Self {
+        self.texture_coords = Some([t1, t2, t3]);
+        self
+    }
+
+    pub fn p1(&self) -> DVec3 {
+        self.p1
+    }
+
+    pub fn p2(&self) -> DVec3 {
+        self.p2
+    }
+
+    pub fn p3(&self) -> DVec3 {
+        self.p3
+    }
+
+    /// Edge from p1 to p2
+    pub fn e1(&self) -> DVec3 {
+        self.e1
+    }
+
+    /// Edge from p1 to p3
+    pub fn e2(&self) -> DVec3 {
+        self.e2
+    }
+
+    pub fn normal(&self) -> DVec3 {
+        self.normal
+    }
+
+    pub fn texture_coords(&self) -> Option<&[DVec2; 3]> {
+        self.texture_coords.as_ref()
+    }
+}
+
+/// A triangle with a normal at each vertex, interpolated across its surface to fake a curved shape
+#[derive(Debug, PartialEq)]
+pub struct SmoothTriangle {
+    triangle: Triangle,
+    n1: DVec3,
+    n2: DVec3,
+    n3: DVec3,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: DVec3, p2: DVec3, p3: DVec3, n1: DVec3, n2: DVec3, n3: DVec3) -> Self {
+        Self {
+            triangle: Triangle::new(p1, p2, p3),
+            n1,
+            n2,
+            n3,
+        }
+    }
+
+    pub fn with_texture_coords(mut self, t1: DVec2, t2: DVec2, t3: DVec2) -> Self {
+        self.triangle = self.triangle.with_texture_coords(t1, t2, t3);
+        self
+    }
+
+    /// The flat triangle going through the vertices
+    pub fn triangle(&self) -> &Triangle {
+        &self.triangle
+    }
+
+    pub fn n1(&self) -> DVec3 {
+        self.n1
+    }
+
+    pub fn n2(&self) -> DVec3 {
+        self.n2
+    }
+
+    pub fn n3(&self) -> DVec3 {
+        self.n3
+    }
+}
+
+
+/// A collection of objects sharing the transform of the group object.
+/// The children are added through the group object so that their transforms stay composed with it.
+#[derive(Debug, Default)]
+pub struct Group {
+    children: Vec<Object>,
+    /// Built on first use, and dropped whenever the children may change
+    bvh: OnceLock<Bvh>,
+}
+
+impl PartialEq for Group {
+    fn eq(&self, other: &Self) -> bool {
+        self.children == other.children
+    }
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn children(&self) -> &Vec<Object> {
+        &self.children
+    }
+
+    pub(crate) fn children_mut(&mut self) -> &mut Vec<Object> {
+        self.bvh.take();
+        &mut self.children
+    }
+
+    /// Hierarchy over the children, in the space of the group
+    pub fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::build(&self.children))
+    }
+}
+
+/// How the two operands of a constructive solid geometry shape are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    /// Everything inside either operand
+    Union,
+    /// Only what is inside both operands
+    Intersection,
+    /// What is inside the left operand but not inside the right one
+    Difference,
+}
+
+/// Two objects combined with a boolean operation, they should be closed for their inside to be well defined.
+/// Like the children of a group, the operands are transformed by the csg object.
+#[derive(Debug, PartialEq)]
+pub struct Csg {
+    operation: CsgOperation,
+    operands: Box<[Object; 2]>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Object, right: Object) -> Self {
+        Self {
+            operation,
+            operands: Box::new([left, right]),
+        }
+    }
+
+    pub fn operation(&self) -> CsgOperation {
+        self.operation
+    }
+
+    pub fn left(&self) -> &Object {
+        &self.operands[0]
+    }
+
+    pub fn right(&self) -> &Object {
+        &self.operands[1]
+    }
+
+    pub fn operands(&self) -> &[Object; 2] {
+        &self.operands
+    }
+
+    pub(crate) fn operands_mut(&mut self) -> &mut [Object; 2] {
+        &mut self.operands
+    }
 }
\ No newline at end of file
@@ -0,0 +1,111 @@
+use crate::{common::BoundingBox, raytracer::cpu::Ray};
+
+impl BoundingBox {
+    /// Distances along the ray where it enters and leaves the box, using the slab method
+    pub fn intersect(&self, ray: &Ray) -> Option<(f64, f64)> {
+        let mut tmin = f64::NEG_INFINITY;
+        let mut tmax = f64::INFINITY;
+
+        for axis in 0..3 {
+            let origin = ray.origin[axis];
+            let direction = ray.direction[axis];
+            let (min, max) = (self.min()[axis], self.max()[axis]);
+
+            if direction == 0.0 {
+                // Parallel to the slab, which is either never or always crossed
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let mut t0 = (min - origin) / direction;
+            let mut t1 = (max - origin) / direction;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            tmin = tmin.max(t0);
+            tmax = tmax.min(t1);
+            if tmin > tmax {
+                return None;
+            }
+        }
+
+        Some((tmin, tmax))
+    }
+
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        self.intersect(ray).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+    use crate::common::shapes::Plane;
+    use super::*;
+
+    #[test]
+    fn intersecting_a_ray_with_a_bounding_box_at_the_origin() {
+        let bb = BoundingBox::new(dvec3(-1.0, -1.0, -1.0), dvec3(1.0, 1.0, 1.0));
+        let examples = [
+            (dvec3(5.0, 0.5, 0.0), dvec3(-1.0, 0.0, 0.0), true),
+            (dvec3(-5.0, 0.5, 0.0), dvec3(1.0, 0.0, 0.0), true),
+            (dvec3(0.5, 5.0, 0.0), dvec3(0.0, -1.0, 0.0), true),
+            (dvec3(0.5, -5.0, 0.0), dvec3(0.0, 1.0, 0.0), true),
+            (dvec3(0.5, 0.0, 5.0), dvec3(0.0, 0.0, -1.0), true),
+            (dvec3(0.5, 0.0, -5.0), dvec3(0.0, 0.0, 1.0), true),
+            (dvec3(0.0, 0.5, 0.0), dvec3(0.0, 0.0, 1.0), true),
+            (dvec3(-2.0, 0.0, 0.0), dvec3(2.0, 4.0, 6.0), false),
+            (dvec3(0.0, -2.0, 0.0), dvec3(6.0, 2.0, 4.0), false),
+            (dvec3(0.0, 0.0, -2.0), dvec3(4.0, 6.0, 2.0), false),
+            (dvec3(2.0, 0.0, 2.0), dvec3(0.0, 0.0, -1.0), false),
+            (dvec3(0.0, 2.0, 2.0), dvec3(0.0, -1.0, 0.0), false),
+            (dvec3(2.0, 2.0, 0.0), dvec3(-1.0, 0.0, 0.0), false),
+        ];
+        for (origin, direction, result) in examples {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(bb.intersects(&r), result, "{} {}", origin, direction);
+        }
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_non_cubic_bounding_box() {
+        let bb = BoundingBox::new(dvec3(5.0, -2.0, 0.0), dvec3(11.0, 4.0, 7.0));
+        let examples = [
+            (dvec3(15.0, 1.0, 2.0), dvec3(-1.0, 0.0, 0.0), true),
+            (dvec3(-5.0, -1.0, 4.0), dvec3(1.0, 0.0, 0.0), true),
+            (dvec3(7.0, 6.0, 5.0), dvec3(0.0, -1.0, 0.0), true),
+            (dvec3(9.0, -5.0, 6.0), dvec3(0.0, 1.0, 0.0), true),
+            (dvec3(8.0, 2.0, 12.0), dvec3(0.0, 0.0, -1.0), true),
+            (dvec3(6.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0), true),
+            (dvec3(8.0, 1.0, 3.5), dvec3(0.0, 0.0, 1.0), true),
+            (dvec3(9.0, -1.0, -8.0), dvec3(2.0, 4.0, 6.0), false),
+            (dvec3(8.0, 3.0, -4.0), dvec3(6.0, 2.0, 4.0), false),
+            (dvec3(9.0, -1.0, -2.0), dvec3(4.0, 6.0, 2.0), false),
+            (dvec3(4.0, 0.0, 9.0), dvec3(0.0, 0.0, -1.0), false),
+            (dvec3(8.0, 6.0, -1.0), dvec3(0.0, -1.0, 0.0), false),
+            (dvec3(12.0, 5.0, 4.0), dvec3(-1.0, 0.0, 0.0), false),
+        ];
+        for (origin, direction, result) in examples {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(bb.intersects(&r), result, "{} {}", origin, direction);
+        }
+    }
+
+    #[test]
+    fn the_entry_and_exit_distances_of_a_ray() {
+        let bb = BoundingBox::new(dvec3(-1.0, -1.0, -1.0), dvec3(1.0, 1.0, 1.0));
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(bb.intersect(&r), Some((4.0, 6.0)));
+    }
+
+    #[test]
+    fn intersecting_infinite_bounds() {
+        let bb = Plane::default().bounds();
+        let r = Ray::new(dvec3(0.0, 1.0, 0.0), dvec3(0.0, -1.0, 0.0));
+        assert_eq!(bb.intersect(&r), Some((1.0, 1.0)));
+        let r = Ray::new(dvec3(0.0, 1.0, 0.0), dvec3(1.0, 0.0, 0.0));
+        assert!(!bb.intersects(&r));
+    }
+}
@@ -0,0 +1,231 @@
+use std::f64::consts::PI;
+
+use glam::DVec3;
+
+use crate::{common::{color::Color, PbrMaterial, Rng}, raytracer::cpu::sampling::{cosine_sample_hemisphere, local_to_world}};
+
+/// Lowest GGX alpha, perfectly smooth surfaces would make the distribution a dirac
+const MIN_ALPHA: f64 = 1.0e-3;
+
+/// An incoming direction drawn from a BRDF
+#[derive(Debug, Clone, Copy)]
+pub struct BrdfSample {
+    pub direction: DVec3,
+    /// brdf * cos / pdf, the factor to apply to the light coming from `direction`
+    pub weight: Color,
+    pub pdf: f64,
+}
+
+/// Metallic-roughness BRDF: Lambertian diffuse plus a GGX (Trowbridge-Reitz) specular lobe
+/// with separable Smith masking-shadowing and Schlick's Fresnel.
+/// `wo` points towards the viewer and `wi` towards the light, both away from the surface.
+impl PbrMaterial {
+    fn alpha(&self) -> f64 {
+        (self.roughness * self.roughness).max(MIN_ALPHA)
+    }
+
+    /// Reflectance at normal incidence
+    fn f0(&self, base_color: Color) -> Color {
+        let dielectric = Color::WHITE * (0.08 * self.specular);
+        dielectric * (1.0 - self.metallic) + base_color * self.metallic
+    }
+
+    /// Probability of sampling the specular lobe rather than the diffuse one
+    fn specular_probability(&self) -> f64 {
+        0.5 + 0.5 * self.metallic
+    }
+
+    /// Value of the BRDF for a pair of directions
+    pub fn eval(&self, base_color: Color, normal: DVec3, wo: DVec3, wi: DVec3) -> Color {
+        let n_dot_o = normal.dot(wo);
+        let n_dot_i = normal.dot(wi);
+        if n_dot_o <= 0.0 || n_dot_i <= 0.0 {
+            return Color::BLACK;
+        }
+
+        let h = (wo + wi).normalize();
+        let alpha = self.alpha();
+        let f0 = self.f0(base_color);
+        let fresnel = schlick(f0, wo.dot(h));
+        let d = ggx_distribution(normal.dot(h), alpha);
+        let g = smith_g1(n_dot_o, alpha) * smith_g1(n_dot_i, alpha);
+
+        let specular = fresnel * (d * g / (4.0 * n_dot_o * n_dot_i));
+        // Light entering and leaving the diffuse layer is what the specular interface did not reflect
+        let transmitted = (Color::WHITE - schlick(f0, n_dot_o)) * (Color::WHITE - schlick(f0, n_dot_i));
+        let diffuse = transmitted * base_color * ((1.0 - self.metallic) / PI);
+        diffuse + specular
+    }
+
+    /// Density of `sample` choosing `wi`, with respect to solid angle
+    pub fn pdf(&self, normal: DVec3, wo: DVec3, wi: DVec3) -> f64 {
+        let n_dot_o = normal.dot(wo);
+        let n_dot_i = normal.dot(wi);
+        if n_dot_o <= 0.0 || n_dot_i <= 0.0 {
+            return 0.0;
+        }
+
+        let h = (wo + wi).normalize();
+        let n_dot_h = normal.dot(h);
+        let specular = ggx_distribution(n_dot_h, self.alpha()) * n_dot_h / (4.0 * wo.dot(h));
+        let diffuse = n_dot_i / PI;
+
+        let p = self.specular_probability();
+        p * specular + (1.0 - p) * diffuse
+    }
+
+    /// Draw an incoming direction, choosing between the GGX half vector distribution and a cosine lobe.
+    /// Returns `None` when the drawn direction ends up under the surface.
+    pub fn sample(&self, base_color: Color, normal: DVec3, wo: DVec3, rng: &mut Rng) -> Option<BrdfSample> {
+        let wi = if rng.next_f64() < self.specular_probability() {
+            let h = sample_ggx_half_vector(normal, self.alpha(), rng);
+            (-wo).reflect(h)
+        } else {
+            cosine_sample_hemisphere(normal, rng)
+        };
+
+        let pdf = self.pdf(normal, wo, wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        let weight = self.eval(base_color, normal, wo, wi) * (normal.dot(wi) / pdf);
+        Some(BrdfSample { direction: wi, weight, pdf })
+    }
+}
+
+fn schlick(f0: Color, cos: f64) -> Color {
+    let factor = (1.0 - cos.clamp(0.0, 1.0)).powi(5);
+    f0 + (Color::WHITE - f0) * factor
+}
+
+/// Trowbridge-Reitz normal distribution
+fn ggx_distribution(n_dot_h: f64, alpha: f64) -> f64 {
+    if n_dot_h <= 0.0 {
+        return 0.0;
+    }
+    let a2 = alpha * alpha;
+    let denom = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * denom * denom)
+}
+
+/// Smith masking function for the GGX distribution
+fn smith_g1(n_dot_v: f64, alpha: f64) -> f64 {
+    let a2 = alpha * alpha;
+    2.0 * n_dot_v / (n_dot_v + (a2 + (1.0 - a2) * n_dot_v * n_dot_v).sqrt())
+}
+
+/// Draw a microfacet normal with a density of D(h) * cos(theta_h)
+fn sample_ggx_half_vector(normal: DVec3, alpha: f64, rng: &mut Rng) -> DVec3 {
+    let u = rng.next_f64();
+    let phi = 2.0 * PI * rng.next_f64();
+    let cos_theta = ((1.0 - u) / (1.0 + (alpha * alpha - 1.0) * u)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let local = DVec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+    local_to_world(local, normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use crate::raytracer::cpu::sampling::uniform_sample_hemisphere;
+
+    use super::*;
+
+    const SAMPLES: usize = 200_000;
+
+    fn outgoing(angle: f64) -> DVec3 {
+        dvec3(angle.sin(), 0.0, angle.cos())
+    }
+
+    /// Fraction of the light reflected towards `wo` under a uniform white environment,
+    /// estimated with the material's own importance sampling
+    fn albedo(material: &PbrMaterial, wo: DVec3) -> Color {
+        let normal = dvec3(0.0, 0.0, 1.0);
+        let mut rng = Rng::new(7);
+        let mut sum = Color::BLACK;
+        for _ in 0..SAMPLES {
+            if let Some(sample) = material.sample(material.base_color, normal, wo, &mut rng) {
+                sum += sample.weight;
+            }
+        }
+        sum / SAMPLES as f64
+    }
+
+    /// Same as `albedo` but with uniformly distributed directions
+    fn uniform_albedo(material: &PbrMaterial, wo: DVec3) -> Color {
+        let normal = dvec3(0.0, 0.0, 1.0);
+        let mut rng = Rng::new(11);
+        let mut sum = Color::BLACK;
+        for _ in 0..SAMPLES {
+            let wi = uniform_sample_hemisphere(normal, &mut rng);
+            sum += material.eval(material.base_color, normal, wo, wi) * (normal.dot(wi) * 2.0 * PI);
+        }
+        sum / SAMPLES as f64
+    }
+
+    #[test]
+    fn a_white_furnace_never_reflects_more_energy_than_it_receives() {
+        for metallic in [0.0, 0.5, 1.0] {
+            for roughness in [0.05, 0.3, 0.6, 1.0] {
+                for specular in [0.0, 0.5, 1.0] {
+                    let m = PbrMaterial::new(Color::WHITE, metallic, roughness).with_specular(specular);
+                    for angle in [0.0, 0.7, 1.3] {
+                        let a = albedo(&m, outgoing(angle));
+                        assert!(a.r <= 1.01, "{:?} at {} reflects {}", m, angle, a.r);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_smooth_white_metal_reflects_almost_everything() {
+        let m = PbrMaterial::new(Color::WHITE, 1.0, 0.1);
+        let a = albedo(&m, outgoing(0.3));
+        assert!(a.r > 0.97 && a.r <= 1.01, "reflects {}", a.r);
+    }
+
+    #[test]
+    fn a_white_rough_dielectric_reflects_most_of_the_light() {
+        let m = PbrMaterial::new(Color::WHITE, 0.0, 1.0);
+        let a = albedo(&m, outgoing(0.0));
+        assert!(a.r > 0.85 && a.r <= 1.01, "reflects {}", a.r);
+    }
+
+    #[test]
+    fn importance_sampling_agrees_with_uniform_sampling() {
+        let materials = [
+            PbrMaterial::new(Color::new(0.8, 0.4, 0.2), 0.0, 0.5),
+            PbrMaterial::new(Color::new(0.9, 0.6, 0.3), 1.0, 0.4),
+            PbrMaterial::new(Color::new(0.5, 0.5, 0.5), 0.3, 0.8),
+        ];
+        for m in materials {
+            let wo = outgoing(0.5);
+            let a = albedo(&m, wo);
+            let b = uniform_albedo(&m, wo);
+            assert!((a.r - b.r).abs() < 0.01 && (a.g - b.g).abs() < 0.01 && (a.b - b.b).abs() < 0.01,
+                "{:?}: {:?} != {:?}", m, a, b);
+        }
+    }
+
+    #[test]
+    fn the_brdf_is_reciprocal() {
+        let m = PbrMaterial::new(Color::new(0.8, 0.4, 0.2), 0.4, 0.3);
+        let normal = dvec3(0.0, 0.0, 1.0);
+        let wo = dvec3(0.3, 0.2, 0.9).normalize();
+        let wi = dvec3(-0.5, 0.1, 0.6).normalize();
+        assert_eq!(m.eval(m.base_color, normal, wo, wi), m.eval(m.base_color, normal, wi, wo));
+    }
+
+    #[test]
+    fn directions_under_the_surface_reflect_nothing() {
+        let m = PbrMaterial::default();
+        let normal = dvec3(0.0, 0.0, 1.0);
+        let wo = dvec3(0.0, 0.0, 1.0);
+        let wi = dvec3(0.0, 0.6, -0.8);
+        assert_eq!(m.eval(m.base_color, normal, wo, wi), Color::BLACK);
+        assert_eq!(m.pdf(normal, wo, wi), 0.0);
+    }
+}
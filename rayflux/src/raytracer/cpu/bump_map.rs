@@ -0,0 +1,165 @@
+use glam::DVec3;
+
+use crate::{common::{shapes::Shape, BumpMap, UvMapping}, raytracer::cpu::sampling::orthonormal_basis};
+
+/// Step used to differentiate height fields and texture coordinates
+const DELTA: f64 = 1.0e-4;
+
+impl BumpMap {
+    /// Perturb a normal at a point on a shape, both in object space
+    pub fn perturb(&self, shape: &Shape, point: DVec3, normal: DVec3) -> DVec3 {
+        match self {
+            BumpMap::Height { pattern, scale } => {
+                let height = |p: DVec3| {
+                    let color = pattern.color_at_on(shape, p);
+                    (color.r + color.g + color.b) / 3.0
+                };
+                let gradient = DVec3::new(
+                    height(point + DVec3::X * DELTA) - height(point - DVec3::X * DELTA),
+                    height(point + DVec3::Y * DELTA) - height(point - DVec3::Y * DELTA),
+                    height(point + DVec3::Z * DELTA) - height(point - DVec3::Z * DELTA),
+                ) / (2.0 * DELTA);
+
+                // Only the slope along the surface tilts the normal
+                let tangential = gradient - normal * gradient.dot(normal);
+                (normal - tangential * *scale).normalize()
+            },
+            BumpMap::NormalMap { texture, mapping, strength } => {
+                let color = texture.sample(mapping.uv_at(shape, point));
+                let x = (color.r * 2.0 - 1.0) * strength;
+                let y = (color.g * 2.0 - 1.0) * strength;
+                let z = color.b * 2.0 - 1.0;
+
+                let (tangent, bitangent) = tangent_frame(mapping, shape, point, normal);
+                let perturbed = tangent * x + bitangent * y + normal * z;
+                if perturbed.length_squared() == 0.0 {
+                    normal
+                } else {
+                    perturbed.normalize()
+                }
+            },
+        }
+    }
+}
+
+/// Unit vectors of the tangent plane pointing towards increasing u and v
+fn tangent_frame(mapping: &UvMapping, shape: &Shape, point: DVec3, normal: DVec3) -> (DVec3, DVec3) {
+    let (t1, t2) = orthonormal_basis(normal);
+
+    // Texture coordinates change along both tangents, ignoring the jump where the mapping wraps around
+    let derivative = |axis: DVec3| {
+        let d = mapping.uv_at(shape, point + axis * DELTA) - mapping.uv_at(shape, point - axis * DELTA);
+        (d - d.round()) / (2.0 * DELTA)
+    };
+    let j1 = derivative(t1);
+    let j2 = derivative(t2);
+
+    let det = j1.x * j2.y - j2.x * j1.y;
+    if det.abs() < f64::MIN_POSITIVE {
+        return (t1, t2);
+    }
+
+    // Invert the jacobian to express the u and v directions with the tangents
+    let dpdu = t1 * (j2.y / det) - t2 * (j1.y / det);
+    let dpdv = t2 * (j1.x / det) - t1 * (j2.x / det);
+
+    let tangent = dpdu.normalize();
+    let bitangent = normal.cross(tangent);
+    if bitangent.dot(dpdv) < 0.0 {
+        (tangent, -bitangent)
+    } else {
+        (tangent, bitangent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glam::dvec3;
+
+    use crate::common::{color::Color, shapes::Plane, ImageTexture, Pattern};
+
+    use super::*;
+
+    const EPSILON: f64 = 0.0001;
+
+    fn flat_texture(color: Color) -> Arc<ImageTexture> {
+        Arc::new(ImageTexture::new(1, 1, vec![color]))
+    }
+
+    fn plane() -> Shape {
+        Shape::Plane(Plane::new())
+    }
+
+    fn assert_close(a: DVec3, b: DVec3) {
+        assert!(a.abs_diff_eq(b, EPSILON), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn a_flat_height_field_keeps_the_normal() {
+        let bump = BumpMap::new_height(Pattern::new_solid(Color::WHITE), 1.0);
+        let n = bump.perturb(&plane(), dvec3(0.3, 0.0, 0.2), dvec3(0.0, 1.0, 0.0));
+        assert_close(n, dvec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn a_slope_tilts_the_normal_away_from_it() {
+        let bump = BumpMap::new_height(Pattern::new_gradient(Color::BLACK, Color::WHITE), 1.0);
+        let n = bump.perturb(&plane(), dvec3(0.5, 0.0, 0.5), dvec3(0.0, 1.0, 0.0));
+        assert_close(n, dvec3(-0.70711, 0.70711, 0.0));
+    }
+
+    #[test]
+    fn the_scale_of_a_height_field_controls_the_tilt() {
+        let bump = BumpMap::new_height(Pattern::new_gradient(Color::BLACK, Color::WHITE), 1.0)
+            .with_strength(0.0);
+        let n = bump.perturb(&plane(), dvec3(0.5, 0.0, 0.5), dvec3(0.0, 1.0, 0.0));
+        assert_close(n, dvec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn a_flat_normal_map_keeps_the_normal() {
+        let bump = BumpMap::new_normal_map(flat_texture(Color::new(0.5, 0.5, 1.0)), UvMapping::Spherical);
+        let normal = dvec3(1.0, 1.0, -1.0).normalize();
+        let n = bump.perturb(&plane(), normal, normal);
+        assert_close(n, normal);
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn a_normal_map_tilts_the_normal_towards_increasing_u() {
+        // Planar mapping has u along x and v along z
+        let bump = BumpMap::new_normal_map(flat_texture(Color::new(1.0, 0.5, 1.0)), UvMapping::Planar);
+        let n = bump.perturb(&plane(), dvec3(0.25, 0.0, 0.25), dvec3(0.0, 1.0, 0.0));
+        assert_close(n, dvec3(0.70711, 0.70711, 0.0));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn a_normal_map_tilts_the_normal_towards_increasing_v() {
+        let bump = BumpMap::new_normal_map(flat_texture(Color::new(0.5, 1.0, 1.0)), UvMapping::Planar);
+        let n = bump.perturb(&plane(), dvec3(0.25, 0.0, 0.25), dvec3(0.0, 1.0, 0.0));
+        assert_close(n, dvec3(0.0, 0.70711, 0.70711));
+    }
+
+    #[test]
+    fn the_tangent_frame_follows_the_spherical_mapping() {
+        // On the equator facing -z, right on the seam where u wraps around,
+        // u increases towards +x and v towards +y
+        let normal = dvec3(0.0, 0.0, -1.0);
+        let (t, b) = tangent_frame(&UvMapping::Spherical, &plane(), normal, normal);
+        assert_close(t, dvec3(1.0, 0.0, 0.0));
+        assert_close(b, dvec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn the_tangent_frame_is_orthonormal_on_a_cube_face() {
+        let normal = dvec3(0.0, 1.0, 0.0);
+        let (t, b) = tangent_frame(&UvMapping::Cube, &plane(), dvec3(0.2, 1.0, 0.3), normal);
+        assert!((t.length() - 1.0).abs() < EPSILON);
+        assert!((b.length() - 1.0).abs() < EPSILON);
+        assert!(t.dot(b).abs() < EPSILON);
+    }
+}
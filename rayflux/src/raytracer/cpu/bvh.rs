@@ -0,0 +1,219 @@
+use crate::{common::{Bvh, BvhNodeKind, Object}, raytracer::cpu::{intersections::{HitPredicate, Hittable, Intersection, Intersections}, Ray}};
+
+impl Bvh {
+    /// Every intersection of the ray with the objects whose bounds it crosses, unsorted
+    pub fn intersect<'a>(&self, ray: &Ray, objects: &'a [Object]) -> Intersections<'a> {
+        let mut intersections = Intersections::new();
+        self.for_each_candidate(ray, |index| intersections.append(objects[index].intersect(ray)));
+        intersections
+    }
+
+    /// Visit the index of every object whose bounds the ray crosses, and of the unbounded ones
+    pub(crate) fn for_each_candidate(&self, ray: &Ray, mut visit: impl FnMut(usize)) {
+        for &index in self.unbounded() {
+            visit(index);
+        }
+
+        if self.nodes().is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes()[node_index];
+            if !node.bounds.intersects(ray) {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { first, count } => {
+                    for &index in self.leaf_objects(first, count) {
+                        visit(index);
+                    }
+                },
+                BvhNodeKind::Interior { left, right } => {
+                    stack.push(right);
+                    stack.push(left);
+                },
+            }
+        }
+    }
+
+    /// Closest intersection before `t_max` accepted by the predicate.
+    /// Nodes are visited front to back and skipped once they are behind the closest hit found so far.
+    pub fn closest_hit<'a>(&self, ray: &Ray, objects: &'a [Object], predicate: impl HitPredicate, t_max: f64) -> Option<Intersection<'a>> {
+        let mut accept = predicate.hit_index_predicate();
+        let mut closest: Option<Intersection<'a>> = None;
+        let mut closest_t = t_max;
+
+        let mut test_object = |object: &'a Object, closest: &mut Option<Intersection<'a>>, closest_t: &mut f64| {
+            for intersection in object.intersect(ray).move_all() {
+                if intersection.t() < *closest_t && accept(&intersection) {
+                    *closest_t = intersection.t();
+                    *closest = Some(intersection);
+                }
+            }
+        };
+
+        for &index in self.unbounded() {
+            test_object(&objects[index], &mut closest, &mut closest_t);
+        }
+
+        let Some(root) = self.nodes().first() else {
+            return closest;
+        };
+
+        // Nodes to visit with the distance at which the ray enters them
+        let mut stack = Vec::new();
+        if let Some((tmin, tmax)) = root.bounds.intersect(ray) {
+            if tmax >= 0.0 {
+                stack.push((0, tmin));
+            }
+        }
+
+        while let Some((node_index, entry)) = stack.pop() {
+            if entry >= closest_t {
+                continue;
+            }
+            match self.nodes()[node_index].kind {
+                BvhNodeKind::Leaf { first, count } => {
+                    for &index in self.leaf_objects(first, count) {
+                        test_object(&objects[index], &mut closest, &mut closest_t);
+                    }
+                },
+                BvhNodeKind::Interior { left, right } => {
+                    let mut children: Vec<(usize, f64)> = [left, right]
+                        .into_iter()
+                        .filter_map(|child| {
+                            let (tmin, tmax) = self.nodes()[child].bounds.intersect(ray)?;
+                            (tmax >= 0.0 && tmin < closest_t).then_some((child, tmin))
+                        })
+                        .collect();
+                    // The nearest child is pushed last to be visited first
+                    children.sort_by(|a, b| b.1.total_cmp(&a.1));
+                    stack.extend(children);
+                },
+            }
+        }
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{dvec3, DVec3};
+    use crate::{common::{Rng, Scene, Transform}, raytracer::cpu::{intersections::{ShadowHit, StandardHit}, sampling::uniform_sample_sphere}};
+    use super::*;
+
+    fn random_spheres(count: usize, rng: &mut Rng) -> Scene {
+        let mut scene = Scene::default();
+        for _ in 0..count {
+            let position = dvec3(rng.next_f64(), rng.next_f64(), rng.next_f64()) * 100.0 - 50.0;
+            let radius = 0.2 + rng.next_f64() * 0.8;
+            scene.add_object(
+                Object::new_sphere()
+                .with_transform(Transform::from_translation(position).with_scale(DVec3::splat(radius)))
+            );
+        }
+        scene
+    }
+
+    fn random_ray(rng: &mut Rng) -> Ray {
+        let origin = dvec3(rng.next_f64(), rng.next_f64(), rng.next_f64()) * 120.0 - 60.0;
+        Ray::new(origin, uniform_sample_sphere(rng))
+    }
+
+    fn brute_force<'a>(ray: &Ray, objects: &'a [Object]) -> Intersections<'a> {
+        let mut intersections = Intersections::new();
+        for object in objects {
+            intersections.append(object.intersect(ray));
+        }
+        intersections.sort()
+    }
+
+    #[test]
+    fn an_empty_scene_has_no_nodes() {
+        let bvh = Bvh::build(&[]);
+        assert!(bvh.nodes().is_empty());
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(bvh.intersect(&r, &[]).count(), 0);
+    }
+
+    #[test]
+    fn planes_are_kept_out_of_the_tree() {
+        let objects = [Object::new_plane(), Object::new_sphere()];
+        let bvh = Bvh::build(&objects);
+        assert_eq!(bvh.unbounded(), &vec![0]);
+        let r = Ray::new(dvec3(0.0, 5.0, 0.0), dvec3(0.0, -1.0, 0.0));
+        let xs = bvh.intersect(&r, &objects).sort();
+        assert_eq!(xs.count(), 3);
+        assert_eq!(xs[0].t(), 4.0);
+        assert_eq!(xs[1].t(), 5.0);
+    }
+
+    #[test]
+    fn the_bvh_finds_the_same_intersections_as_brute_force() {
+        let mut rng = Rng::new(7);
+        let mut scene = random_spheres(500, &mut rng);
+        scene.add_object(Object::new_plane().with_transform(Transform::from_translation(dvec3(0.0, -50.0, 0.0))));
+        let objects = scene.objects();
+        for _ in 0..500 {
+            let ray = random_ray(&mut rng);
+            let expected = brute_force(&ray, objects);
+            let result = scene.bvh().intersect(&ray, objects).sort();
+            assert_eq!(result.count(), expected.count());
+            for (a, b) in result.get_all().iter().zip(expected.get_all()) {
+                assert_eq!(a.t(), b.t());
+                assert!(std::ptr::eq(a.object(), b.object()));
+            }
+        }
+    }
+
+    #[test]
+    fn the_closest_hit_is_the_first_hit_of_brute_force() {
+        let mut rng = Rng::new(11);
+        let mut scene = random_spheres(500, &mut rng);
+        scene.objects_mut()[3].set_shadow(false);
+        let objects = scene.objects();
+        for _ in 0..500 {
+            let ray = random_ray(&mut rng);
+            let expected = brute_force(&ray, objects);
+
+            let hit = scene.bvh().closest_hit(&ray, objects, StandardHit {}, f64::INFINITY);
+            assert_eq!(hit.as_ref(), expected.hit(StandardHit {}));
+
+            let shadow = scene.bvh().closest_hit(&ray, objects, ShadowHit {}, 30.0);
+            assert_eq!(shadow.as_ref(), expected.hit(ShadowHit {}).filter(|hit| hit.t() < 30.0));
+        }
+    }
+
+    #[test]
+    fn the_bvh_tests_a_fraction_of_the_objects() {
+        let mut rng = Rng::new(3);
+        let scene = random_spheres(5000, &mut rng);
+        let objects = scene.objects();
+        let rays: Vec<Ray> = (0..500).map(|_| random_ray(&mut rng)).collect();
+
+        let mut tested = 0;
+        for ray in &rays {
+            scene.bvh().for_each_candidate(ray, |_| tested += 1);
+            assert_eq!(scene.bvh().intersect(ray, objects).count(), brute_force(ray, objects).count());
+        }
+        assert!(tested * 100 < rays.len() * objects.len(), "{} tests for {} rays", tested, rays.len());
+    }
+
+    #[test]
+    fn the_bvh_is_rebuilt_when_objects_are_added() {
+        let mut scene = Scene::default();
+        scene.add_object(Object::new_sphere());
+        let r = Ray::new(dvec3(10.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(scene.bvh().intersect(&r, scene.objects()).count(), 0);
+
+        scene.add_object(Object::new_sphere().with_transform(Transform::from_translation(dvec3(10.0, 0.0, 0.0))));
+        assert_eq!(scene.bvh().intersect(&r, scene.objects()).count(), 2);
+
+        let mut transform = *scene.objects()[1].transform();
+        transform.translate(dvec3(0.0, 10.0, 0.0));
+        scene.objects_mut()[1].set_transform(transform);
+        assert_eq!(scene.bvh().intersect(&r, scene.objects()).count(), 0);
+    }
+}
@@ -13,15 +13,7 @@ fn scale_color(color: Color) -> (u8, u8, u8) {
 }
 
 fn scale_color_component(component: f64) -> u8 {
-    let component = if component < 0.0 {
-        0.0
-    } else if component > 1.0 {
-        1.0
-    } else {
-        component
-    };
-
-    (component * 255.0) as u8
+    (component.clamp(0.0, 1.0) * 255.0) as u8
 }
 
 pub struct Canvas {
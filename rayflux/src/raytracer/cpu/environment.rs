@@ -0,0 +1,85 @@
+use std::f64::consts::PI;
+
+use glam::{DVec2, DVec3};
+
+use crate::common::{color::Color, EnvironmentMap};
+
+impl EnvironmentMap {
+    /// Texture coordinates of a world space direction.
+    /// The center of the image is seen when looking toward -z, the top when looking toward +y.
+    pub fn uv_at(&self, direction: DVec3) -> DVec2 {
+        let d = self
+            .transform()
+            .inverse_matrix()
+            .transform_vector3(direction)
+            .normalize();
+        let u = 0.5 + d.x.atan2(-d.z) / (2.0 * PI);
+        let v = 0.5 + d.y.clamp(-1.0, 1.0).asin() / PI;
+        DVec2::new(u, v)
+    }
+
+    /// Radiance coming from a world space direction
+    pub fn color_at(&self, direction: DVec3) -> Color {
+        self.texture().sample(self.uv_at(direction)) * self.intensity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{dvec2, dvec3};
+
+    use crate::common::{ImageTexture, TextureFilter, Transform};
+
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    fn environment() -> EnvironmentMap {
+        // 4x2 texture, the top row is the sky and the bottom row the ground
+        let texture = ImageTexture::new(4, 2, vec![
+            Color::RED, Color::GREEN, Color::BLUE, Color::WHITE,
+            Color::BLACK, Color::BLACK, Color::BLACK, Color::BLACK,
+        ])
+        .with_filter(TextureFilter::Nearest);
+        EnvironmentMap::new(texture)
+    }
+
+    #[test]
+    fn mapping_directions_to_equirectangular_coordinates() {
+        let env = environment();
+        let examples = [
+            (dvec3(0.0, 0.0, -1.0), dvec2(0.5, 0.5)),
+            (dvec3(1.0, 0.0, 0.0), dvec2(0.75, 0.5)),
+            (dvec3(-1.0, 0.0, 0.0), dvec2(0.25, 0.5)),
+            (dvec3(0.0, 1.0, -1.0), dvec2(0.5, 0.75)),
+        ];
+        for (direction, uv) in examples {
+            assert!(env.uv_at(direction).abs_diff_eq(uv, EPSILON));
+        }
+        // u is undefined at the poles
+        assert!((env.uv_at(dvec3(0.0, 1.0, 0.0)).y - 1.0).abs() < EPSILON);
+        assert!(env.uv_at(dvec3(0.0, -2.0, 0.0)).y.abs() < EPSILON);
+    }
+
+    #[test]
+    fn sampling_the_environment() {
+        let env = environment();
+        assert_eq!(env.color_at(dvec3(-1.0, 0.1, 0.0)), Color::GREEN);
+        assert_eq!(env.color_at(dvec3(1.0, 0.1, 0.0)), Color::WHITE);
+        assert_eq!(env.color_at(dvec3(1.0, -0.1, 0.0)), Color::BLACK);
+    }
+
+    #[test]
+    fn rotating_the_environment() {
+        let env = environment()
+            .with_transform(Transform::new().with_rotation_y(std::f64::consts::FRAC_PI_2));
+        // What was seen toward -z is now seen toward -x
+        assert!(env.uv_at(dvec3(-1.0, 0.0, 0.0)).abs_diff_eq(dvec2(0.5, 0.5), EPSILON));
+    }
+
+    #[test]
+    fn the_intensity_scales_the_environment() {
+        let env = environment().with_intensity(2.0);
+        assert_eq!(env.color_at(dvec3(1.0, 0.1, 0.0)), Color::new(2.0, 2.0, 2.0));
+    }
+}
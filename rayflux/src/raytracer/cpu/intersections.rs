@@ -1,18 +1,33 @@
 use std::cmp::Ordering;
 
-use glam::DVec3;
+use glam::{DVec2, DVec3};
 
-use crate::{common::{Object, Scene}, raytracer::cpu::Ray};
+use crate::{common::{Object, Scene}, raytracer::cpu::{Ray, EPSILON}};
 
 pub trait Hittable {
     /// Intersect an object with a ray and return the resulting intersections
     fn intersect<'a>(&'a self, ray: &Ray) -> Intersections<'a>; 
-    fn normal_at<'a>(&self, point: DVec3) -> DVec3; 
+    /// Shading normal at a world space point, including the material's bump map
+    fn normal_at(&self, point: DVec3) -> DVec3; 
+    /// Same as `normal_at`, using the hit informations for the shapes interpolating their normals
+    fn normal_at_hit(&self, point: DVec3, hit: &Intersection) -> DVec3;
+    /// Normal of the actual geometry at a world space point
+    fn geometric_normal_at(&self, point: DVec3) -> DVec3;
+    /// Convert a point from world space to object space
+    fn world_to_object(&self, point: DVec3) -> DVec3;
+    /// Texture coordinates of a world space point on the surface
+    fn uv_at(&self, point: DVec3) -> DVec2;
 }
 
 pub trait HittableShape {
     fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a>; 
-    fn normal_at<'a>(&self, point: DVec3) -> DVec3; 
+    fn normal_at(&self, point: DVec3) -> DVec3; 
+    /// Shading normal at an object space point, for the shapes that need the hit informations to compute it
+    fn normal_at_hit(&self, point: DVec3, _hit: &Intersection) -> DVec3 {
+        self.normal_at(point)
+    }
+    /// Texture coordinates of an object space point on the surface
+    fn uv_at(&self, point: DVec3) -> DVec2;
 }
 
 impl Hittable for Object {
@@ -21,20 +36,62 @@ impl Hittable for Object {
         self.shape().intersect(&local_ray, self)
     }
 
-    fn normal_at<'a>(&self, point: glam::DVec3) -> DVec3 {
-        let transform = self.transform();
-        let local_point = transform.inverse_matrix().transform_point3(point);
+    fn normal_at(&self, point: DVec3) -> DVec3 {
+        let local_point = self.world_to_object(point);
+        let local_normal = self.shape().normal_at(local_point);
+        shading_normal(self, local_point, local_normal)
+    }
+
+    fn normal_at_hit(&self, point: DVec3, hit: &Intersection) -> DVec3 {
+        let local_point = self.world_to_object(point);
+        let local_normal = self.shape().normal_at_hit(local_point, hit);
+        shading_normal(self, local_point, local_normal)
+    }
+
+    fn geometric_normal_at(&self, point: DVec3) -> DVec3 {
+        let local_point = self.world_to_object(point);
         let local_normal = self.shape().normal_at(local_point);
-        transform.inverse_transpose_matrix().transform_vector3(local_normal).normalize()
+        self.world_inverse_transpose_matrix().transform_vector3(local_normal).normalize()
+    }
+
+    fn world_to_object(&self, point: DVec3) -> DVec3 {
+        self.world_inverse_matrix().transform_point3(point)
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        self.shape().uv_at(self.world_to_object(point))
     }
 }
 
+/// Apply the bump map of an object to a normal and bring it to world space
+fn shading_normal(object: &Object, local_point: DVec3, mut local_normal: DVec3) -> DVec3 {
+    if let Some(bump) = &object.material().bump {
+        local_normal = bump.perturb(object.shape(), local_point, local_normal);
+    }
+    object.world_inverse_transpose_matrix().transform_vector3(local_normal).normalize()
+}
+
+/// Every intersection of a ray with the scene, sorted by distance
 pub fn intersect_scene<'a>(ray: &Ray, scene: &'a Scene) -> Intersections<'a> {
-    let mut intersections = Intersections::new();
-    for object in scene.objects().iter() {
-       intersections.append(object.intersect(ray));
+    scene.bvh().intersect(ray, scene.objects()).sort()
+}
+
+/// Closest intersection of a ray with the scene before `t_max` that is accepted by the predicate
+pub fn closest_hit<'a>(ray: &Ray, scene: &'a Scene, predicate: impl HitPredicate, t_max: f64) -> Option<Intersection<'a>> {
+    scene.bvh().closest_hit(ray, scene.objects(), predicate, t_max)
+}
+
+/// Intersections needed to shade the first surface hit by a ray.
+/// Only the hit is kept, unless the media or the refractive indices need every intersection along the ray.
+pub fn shading_intersections<'a>(ray: &Ray, scene: &'a Scene) -> Intersections<'a> {
+    if scene.has_media() {
+        return intersect_scene(ray, scene);
+    }
+    match closest_hit(ray, scene, StandardHit {}, f64::INFINITY) {
+        Some(hit) if hit.object.material().transparency > 0.0 => intersect_scene(ray, scene),
+        Some(hit) => Intersections::new().with_intersections(vec![hit]),
+        None => Intersections::new(),
     }
-    intersections.sort()
 }
 
 pub trait HitPredicate {
@@ -42,28 +99,29 @@ pub trait HitPredicate {
     fn hit_index_predicate(&self) -> Box<dyn FnMut(&Intersection<'_>) -> bool>;
 }
 
+/// First surface in front of the ray, the boundaries of media are not surfaces
 pub struct StandardHit {}
 
 impl HitPredicate for StandardHit {
     fn hit_predicate(&self) -> Box<dyn FnMut(&&Intersection<'_>) -> bool> {
-       Box::new(|i| i.t >= 0.0)
+       Box::new(|i| i.object.medium().is_none() && i.t >= 0.0)
     }
 
     fn hit_index_predicate(&self) -> Box<dyn FnMut(&Intersection<'_>) -> bool> {
-        Box::new(|i| i.t >= 0.0)
+        Box::new(|i| i.object.medium().is_none() && i.t >= 0.0)
     }
 }
 
+/// First surface in front of the ray that casts shadows, media only attenuate the light
 pub struct ShadowHit {}
 
 impl HitPredicate for ShadowHit {
     fn hit_predicate(&self) -> Box<dyn FnMut(&&Intersection<'_>) -> bool> {
-        //Box::new(|i| i.object.shadow() && i.t >= 0.0)
-        Box::new(|i| i.t >= 0.0)
+        Box::new(|i| i.object.shadow() && i.object.medium().is_none() && i.t >= 0.0)
     }
 
     fn hit_index_predicate(&self) -> Box<dyn FnMut(&Intersection<'_>) -> bool> {
-        Box::new(|i| i.t >= 0.0)
+        Box::new(|i| i.object.shadow() && i.object.medium().is_none() && i.t >= 0.0)
     }
 }
 
@@ -71,6 +129,9 @@ impl HitPredicate for ShadowHit {
 pub struct Intersection<'a> {
     t: f64,
     object: &'a Object,
+    /// Barycentric coordinates of the hit on a triangle, coordinates along the edges of a quad, 0 on other shapes
+    u: f64,
+    v: f64,
 }
 
 impl<'a> Intersection<'a> {
@@ -78,6 +139,17 @@ impl<'a> Intersection<'a> {
         Self {
             t,
             object,
+            u: 0.0,
+            v: 0.0,
+        }
+    }
+
+    pub fn new_with_uv(t: f64, object: &'a Object, u: f64, v: f64) -> Self {
+        Self {
+            t,
+            object,
+            u,
+            v,
         }
     }
 
@@ -85,8 +157,16 @@ impl<'a> Intersection<'a> {
         self.t
     }
 
+    pub fn u(&self) -> f64 {
+        self.u
+    }
+
+    pub fn v(&self) -> f64 {
+        self.v
+    }
+
     pub fn object(&self) -> &Object {
-        &self.object
+        self.object
     }
 }
 
@@ -99,28 +179,27 @@ impl<'a> PartialOrd for Intersection<'a> {
 impl<'a> Ord for Intersection<'a> {
     fn cmp(&self, other: &Self) -> Ordering {
         if self.t < other.t {
-            return Ordering::Less;
+            Ordering::Less
         }
         else if self.t > other.t {
-            return Ordering::Greater;
+            Ordering::Greater
         }
         else {
-            return Ordering::Equal;
+            Ordering::Equal
         }
     }
 }
 
 impl<'a> Eq for Intersection<'a> {}
 
+#[derive(Default)]
 pub struct Intersections<'a> {
     intersections: Vec<Intersection<'a>>,
 }
 
 impl<'a> Intersections<'a> {
     pub fn new() -> Self {
-        Self {  
-            intersections: Vec::default()
-        }
+        Self::default()
     }
 
     pub fn from_capacity(size: usize) -> Self {
@@ -147,7 +226,7 @@ impl<'a> Intersections<'a> {
         self
     }
 
-    pub fn hit(&self, predicate: impl HitPredicate) -> Option<&Intersection> {
+    pub fn hit(&self, predicate: impl HitPredicate) -> Option<&Intersection<'_>> {
         self.intersections.iter().find(predicate.hit_predicate())
     }
 
@@ -159,7 +238,7 @@ impl<'a> Intersections<'a> {
         self.intersections.len()
     }
 
-    pub fn get(&self, index: usize) -> Option<&Intersection> {
+    pub fn get(&self, index: usize) -> Option<&Intersection<'_>> {
         self.intersections.get(index)
     }
 
@@ -186,7 +265,19 @@ pub struct IntersectionInfos<'a> {
     pub t: f64,
     pub object: &'a Object,
     pub point: DVec3,
+    /// Point slightly above the surface, used as origin for secondary rays to avoid self intersections
+    pub over_point: DVec3,
+    /// Point slightly below the surface, used as origin for refracted rays
+    pub under_point: DVec3,
+    pub eyev: DVec3,
     pub normal: DVec3,
+    /// Direction of the incoming ray reflected around the normal
+    pub reflectv: DVec3,
+    pub inside: bool,
+    /// Refractive index of the material the ray is leaving
+    pub n1: f64,
+    /// Refractive index of the material the ray is entering
+    pub n2: f64,
 }
 
 impl<'a> IntersectionInfos<'a> {
@@ -196,26 +287,126 @@ impl<'a> IntersectionInfos<'a> {
         let point = ray.at(t);
         let eyev = -ray.direction;
         let object = intersection.object;
-        let mut normal = object.normal_at(point);
+        let mut geometric_normal = object.geometric_normal_at(point);
         let mut inside = false;
-        if normal.dot(eyev) < 0.0 {
+        if geometric_normal.dot(eyev) < 0.0 {
             inside = true;
+            geometric_normal = -geometric_normal;
+        }
+        let mut normal = object.normal_at_hit(point, intersection);
+        if inside {
             normal = -normal;
         }
+        // Offset along the geometric normal, a perturbed normal could point back into the surface
+        let over_point = point + geometric_normal * EPSILON;
+        let under_point = point - geometric_normal * EPSILON;
+        let reflectv = ray.direction.reflect(normal);
+        let (n1, n2) = Self::refractive_indices(intersections, intersection_index);
 
         Self {
             t,
             object,
             point,
+            over_point,
+            under_point,
+            eyev,
             normal,
+            reflectv,
+            inside,
+            n1,
+            n2,
+        }
+    }
+
+    /// Find the refractive indices on both sides of the hit by walking the sorted intersections
+    /// and keeping track of the objects containing the current point
+    fn refractive_indices(intersections: &Intersections, intersection_index: usize) -> (f64, f64) {
+        let mut containers: Vec<&Object> = Vec::new();
+        let mut n1 = 1.0;
+        let mut n2 = 1.0;
+
+        for (index, intersection) in intersections.get_all().iter().enumerate() {
+            if index == intersection_index {
+                n1 = containers
+                    .last()
+                    .map_or(1.0, |object| object.material().refractive_index);
+            }
+
+            match containers.iter().position(|object| std::ptr::eq(*object, intersection.object)) {
+                Some(position) => { containers.remove(position); },
+                None => containers.push(intersection.object),
+            }
+
+            if index == intersection_index {
+                n2 = containers
+                    .last()
+                    .map_or(1.0, |object| object.material().refractive_index);
+                break;
+            }
+        }
+
+        (n1, n2)
+    }
+
+    /// Fraction of the light that is reflected, using Schlick's approximation of the Fresnel equations
+    pub fn schlick(&self) -> f64 {
+        let mut cos = self.eyev.dot(self.normal);
+
+        // Total internal reflection can only occur if n1 > n2
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n * n * (1.0 - cos * cos);
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+
+            // When n1 > n2, use cos(theta_t) instead
+            cos = (1.0 - sin2_t).sqrt();
         }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+
+    /// Direction of the ray transmitted through the surface following Snell's law,
+    /// or None under total internal reflection
+    pub fn refractv(&self) -> Option<DVec3> {
+        let n_ratio = self.n1 / self.n2;
+        let cos_i = self.eyev.dot(self.normal);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            return None;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(self.normal * (n_ratio * cos_i - cos_t) - self.eyev * n_ratio)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use glam::dvec3;
+
+    use crate::common::{color::Color, BumpMap, Material, Pattern, Transform};
+
     use super::*;
 
+    #[test]
+    fn moving_an_object_moves_its_intersections_and_its_normals() {
+        let mut o = Object::new_sphere();
+        let mut transform = *o.transform();
+        transform.translate(dvec3(0.0, 0.0, 5.0));
+        o.set_transform(transform);
+
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = o.intersect(&r);
+        assert_eq!(xs.count(), 2);
+        assert_eq!(xs[0].t(), 9.0);
+        let point = r.at(xs[0].t());
+        assert_eq!(o.normal_at(point), dvec3(0.0, 0.0, -1.0));
+        assert_eq!(o.world_to_object(point), dvec3(0.0, 0.0, -1.0));
+    }
+
     // An intersection encapsulate t and object
     #[test]
     fn new_intersection() {
@@ -225,6 +416,133 @@ mod tests {
         assert_eq!(i.object, &o);
     }
 
+    #[test]
+    fn the_hit_should_offset_the_point() {
+        let r = Ray::new(DVec3::new(0.0, 0.0, -5.0), DVec3::new(0.0, 0.0, 1.0));
+        let o = Object::new_sphere()
+            .with_transform(
+                Transform::from_translation(DVec3::new(0.0, 0.0, 1.0))
+            );
+        let xs = Intersections::new().with_intersections(vec![Intersection::new(5.0, &o)]);
+        let infos = IntersectionInfos::new(&xs, 0, &r);
+        assert!(infos.over_point.z < -EPSILON / 2.0);
+        assert!(infos.point.z > infos.over_point.z);
+    }
+
+    #[test]
+    fn precomputing_the_reflection_vector() {
+        let o = Object::new_plane();
+        let r = Ray::new(
+            DVec3::new(0.0, 1.0, -1.0),
+            DVec3::new(0.0, -2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0)
+        );
+        let xs = Intersections::new().with_intersections(vec![Intersection::new(2.0_f64.sqrt(), &o)]);
+        let infos = IntersectionInfos::new(&xs, 0, &r);
+        assert!(infos.reflectv.abs_diff_eq(DVec3::new(0.0, 2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0), EPSILON));
+    }
+
+    fn glass_sphere() -> Object {
+        Object::new_sphere()
+        .with_material(
+            Material::new()
+            .with_transparency(1.0)
+            .with_refractive_index(1.5)
+        )
+    }
+
+    #[test]
+    fn finding_n1_and_n2_at_various_intersections() {
+        let a = glass_sphere()
+            .with_transform(Transform::from_scale(DVec3::new(2.0, 2.0, 2.0)));
+        let mut b = glass_sphere()
+            .with_transform(Transform::from_translation(DVec3::new(0.0, 0.0, -0.25)));
+        b.material_mut().refractive_index = 2.0;
+        let mut c = glass_sphere()
+            .with_transform(Transform::from_translation(DVec3::new(0.0, 0.0, 0.25)));
+        c.material_mut().refractive_index = 2.5;
+        let r = Ray::new(DVec3::new(0.0, 0.0, -4.0), DVec3::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new().with_intersections(vec![
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ]);
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (index, (n1, n2)) in expected.iter().enumerate() {
+            let infos = IntersectionInfos::new(&xs, index, &r);
+            assert_eq!(infos.n1, *n1);
+            assert_eq!(infos.n2, *n2);
+        }
+    }
+
+    #[test]
+    fn the_under_point_is_offset_below_the_surface() {
+        let r = Ray::new(DVec3::new(0.0, 0.0, -5.0), DVec3::new(0.0, 0.0, 1.0));
+        let o = glass_sphere()
+            .with_transform(
+                Transform::from_translation(DVec3::new(0.0, 0.0, 1.0))
+            );
+        let xs = Intersections::new().with_intersections(vec![Intersection::new(5.0, &o)]);
+        let infos = IntersectionInfos::new(&xs, 0, &r);
+        assert!(infos.under_point.z > EPSILON / 2.0);
+        assert!(infos.point.z < infos.under_point.z);
+    }
+
+    #[test]
+    fn the_schlick_approximation_under_total_internal_reflection() {
+        let o = glass_sphere();
+        let r = Ray::new(DVec3::new(0.0, 0.0, 2.0_f64.sqrt() / 2.0), DVec3::new(0.0, 1.0, 0.0));
+        let xs = Intersections::new().with_intersections(vec![
+            Intersection::new(-2.0_f64.sqrt() / 2.0, &o),
+            Intersection::new(2.0_f64.sqrt() / 2.0, &o),
+        ]);
+        let infos = IntersectionInfos::new(&xs, 1, &r);
+        assert_eq!(infos.schlick(), 1.0);
+    }
+
+    #[test]
+    fn the_schlick_approximation_with_a_perpendicular_viewing_angle() {
+        let o = glass_sphere();
+        let r = Ray::new(DVec3::new(0.0, 0.0, 0.0), DVec3::new(0.0, 1.0, 0.0));
+        let xs = Intersections::new().with_intersections(vec![
+            Intersection::new(-1.0, &o),
+            Intersection::new(1.0, &o),
+        ]);
+        let infos = IntersectionInfos::new(&xs, 1, &r);
+        assert!((infos.schlick() - 0.04).abs() < EPSILON);
+    }
+
+    #[test]
+    fn the_schlick_approximation_with_small_angle_and_n2_greater_than_n1() {
+        let o = glass_sphere();
+        let r = Ray::new(DVec3::new(0.0, 0.99, -2.0), DVec3::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new().with_intersections(vec![Intersection::new(1.8589, &o)]);
+        let infos = IntersectionInfos::new(&xs, 0, &r);
+        assert!((infos.schlick() - 0.48873).abs() < EPSILON);
+    }
+
+    #[test]
+    fn the_shadow_hit_ignores_objects_without_shadows() {
+        let o1 = Object::new_sphere().with_shadow(false);
+        let o2 = Object::new_sphere();
+        let xs = Intersections::new().with_intersections(vec![
+            Intersection::new(1.0, &o1),
+            Intersection::new(2.0, &o2),
+        ]);
+        assert_eq!(xs.hit(ShadowHit {}).unwrap().t(), 2.0);
+        assert_eq!(xs.hit_index(ShadowHit {}), Some(1));
+        assert_eq!(xs.hit_index(StandardHit {}), Some(0));
+    }
+
     #[test]
     fn intersections_is_empty_by_default() {
         let xs = Intersections::new();
@@ -254,4 +572,47 @@ mod tests {
         assert_eq!(xs.get(0), Some(&i1));
         assert_eq!(xs.get(1), Some(&i2));
     }
-}
\ No newline at end of file
+
+    fn bumped_plane() -> Object {
+        // Height rising along x, tilting the normal towards -x
+        Object::new_plane()
+            .with_material(
+                Material::new()
+                .with_bump(BumpMap::new_height(Pattern::new_gradient(Color::BLACK, Color::WHITE), 1.0))
+            )
+    }
+
+    #[test]
+    fn the_bump_map_perturbs_the_normal_in_object_space() {
+        let o = bumped_plane()
+            .with_transform(Transform::from_scale(dvec3(2.0, 1.0, 1.0)));
+        let n = o.normal_at(dvec3(1.0, 0.0, 0.0));
+        assert!(n.abs_diff_eq(dvec3(-0.44721, 0.89443, 0.0), 0.0001), "{}", n);
+        assert_eq!(o.geometric_normal_at(dvec3(1.0, 0.0, 0.0)), dvec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn intersection_infos_use_the_perturbed_normal() {
+        let o = bumped_plane();
+        let r = Ray::new(dvec3(0.5, 1.0, 0.0), dvec3(0.0, -1.0, 0.0));
+        let xs = Intersections::new().with_intersections(vec![Intersection::new(1.0, &o)]);
+        let infos = IntersectionInfos::new(&xs, 0, &r);
+        assert!(infos.normal.abs_diff_eq(dvec3(-0.70711, 0.70711, 0.0), 0.0001));
+        assert!(!infos.inside);
+        // Secondary rays still start right above the actual surface
+        assert_eq!(infos.over_point, dvec3(0.5, EPSILON, 0.0));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn the_perturbed_normal_is_flipped_inside_objects() {
+        let o = bumped_plane();
+        let r = Ray::new(dvec3(0.5, -1.0, 0.0), dvec3(0.0, 1.0, 0.0));
+        let xs = Intersections::new().with_intersections(vec![Intersection::new(1.0, &o)]);
+        let infos = IntersectionInfos::new(&xs, 0, &r);
+        assert!(infos.inside);
+        assert!(infos.normal.abs_diff_eq(dvec3(0.70711, -0.70711, 0.0), 0.0001));
+        assert_eq!(infos.over_point, dvec3(0.5, -EPSILON, 0.0));
+    }
+}
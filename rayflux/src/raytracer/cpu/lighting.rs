@@ -0,0 +1,278 @@
+use std::f64::consts::PI;
+
+use glam::{DVec2, DVec3};
+
+use crate::common::{color::Color, Light, LightSample, Material, Object};
+
+/// Compute the color of a surface point lit by a light using the Phong reflection model,
+/// or the material's BRDF when it is physically based.
+/// `visibility` is the fraction of the light reaching the point, 0 meaning fully in shadow,
+/// in which case only the ambient term remains.
+pub fn lighting(material: &Material, object: &Object, light: &Light, point: DVec3, eyev: DVec3, normalv: DVec3, visibility: f64) -> Color {
+    let color = surface_color(material, object, point);
+
+    let ambient = color * light.intensity() * material.ambient;
+    if visibility == 0.0 {
+        return ambient;
+    }
+
+    ambient + direct_lighting(material, color, light, point, eyev, normalv) * visibility
+}
+
+/// Color of the material at a world space point, from its pattern if it has one
+pub fn surface_color(material: &Material, object: &Object, point: DVec3) -> Color {
+    match &material.pattern {
+        Some(pattern) => pattern.color_at_object(object, point),
+        None => material.pbr.map_or(material.color, |pbr| pbr.base_color),
+    }
+}
+
+/// Diffuse and specular light reflected towards the eye by a surface of the given color,
+/// assuming the light is fully visible
+pub fn direct_lighting(material: &Material, color: Color, light: &Light, point: DVec3, eyev: DVec3, normalv: DVec3) -> Color {
+    // Diffuse and specular contribution of a single sample of the light
+    let contribution = |sample: LightSample| {
+        // Combine the surface color with the light's color/intensity
+        let effective_color = color * sample.intensity;
+        let lightv = sample.direction;
+
+        // A negative value means the light is on the other side of the surface
+        let light_dot_normal = lightv.dot(normalv);
+        if light_dot_normal < 0.0 {
+            return Color::BLACK;
+        }
+
+        // Light intensities are scaled by pi so that a white lambertian surface facing
+        // a unit light reflects 1, like the Phong diffuse term does
+        if let Some(pbr) = &material.pbr {
+            return pbr.eval(color, normalv, eyev, lightv) * sample.intensity * (light_dot_normal * PI);
+        }
+
+        let diffuse = effective_color * material.diffuse * light_dot_normal;
+
+        // A negative value means the light reflects away from the eye
+        let reflectv = (-lightv).reflect(normalv);
+        let reflect_dot_eye = reflectv.dot(eyev);
+        let specular = if reflect_dot_eye <= 0.0 {
+            Color::BLACK
+        } else {
+            let factor = reflect_dot_eye.powf(material.shininess);
+            sample.intensity * material.specular * factor
+        };
+
+        diffuse + specular
+    };
+
+    // Average the contribution of every sample, using the center of the cells for area lights
+    let samples = light.sample_count();
+    let mut sum = Color::BLACK;
+    for index in 0..samples {
+        sum += contribution(light.sample(point, index, DVec2::splat(0.5)));
+    }
+
+    sum / samples as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use crate::common::{AreaLight, DirectionalLight, Pattern, PbrMaterial, PointLight, SpotLight};
+
+    use super::*;
+
+    fn setup() -> (Material, DVec3) {
+        (Material::default(), DVec3::ZERO)
+    }
+
+    #[test]
+    fn lighting_with_the_eye_between_the_light_and_the_surface() {
+        let (m, position) = setup();
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        let result = lighting(&m, &Object::new_sphere(), &light, position, eyev, normalv, 1.0);
+        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn lighting_with_the_eye_between_light_and_surface_eye_offset_45() {
+        let (m, position) = setup();
+        let eyev = dvec3(0.0, 2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        let result = lighting(&m, &Object::new_sphere(), &light, position, eyev, normalv, 1.0);
+        assert_eq!(result, Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn lighting_with_eye_opposite_surface_light_offset_45() {
+        let (m, position) = setup();
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 10.0, -10.0), Color::WHITE));
+        let result = lighting(&m, &Object::new_sphere(), &light, position, eyev, normalv, 1.0);
+        assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
+    }
+
+    #[test]
+    fn lighting_with_eye_in_the_path_of_the_reflection_vector() {
+        let (m, position) = setup();
+        let eyev = dvec3(0.0, -2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 10.0, -10.0), Color::WHITE));
+        let result = lighting(&m, &Object::new_sphere(), &light, position, eyev, normalv, 1.0);
+        assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
+    }
+
+    #[test]
+    fn lighting_with_the_light_behind_the_surface() {
+        let (m, position) = setup();
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 0.0, 10.0), Color::WHITE));
+        let result = lighting(&m, &Object::new_sphere(), &light, position, eyev, normalv, 1.0);
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_with_the_surface_in_shadow() {
+        let (m, position) = setup();
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        let result = lighting(&m, &Object::new_sphere(), &light, position, eyev, normalv, 0.0);
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_with_a_pattern_applied() {
+        let m = Material::new()
+            .with_pattern(Pattern::new_stripe(Color::WHITE, Color::BLACK))
+            .with_ambient(1.0)
+            .with_diffuse(0.0)
+            .with_specular(0.0);
+        let o = Object::new_sphere();
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        let c1 = lighting(&m, &o, &light, dvec3(0.9, 0.0, 0.0), eyev, normalv, 1.0);
+        let c2 = lighting(&m, &o, &light, dvec3(1.1, 0.0, 0.0), eyev, normalv, 1.0);
+        assert_eq!(c1, Color::WHITE);
+        assert_eq!(c2, Color::BLACK);
+    }
+
+    #[test]
+    fn lighting_uses_light_visibility_to_attenuate_color() {
+        let m = Material::new()
+            .with_ambient(0.1)
+            .with_diffuse(0.9)
+            .with_specular(0.0);
+        let o = Object::new_sphere();
+        let light = Light::from(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        let point = dvec3(0.0, 0.0, -1.0);
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let examples = [
+            (1.0, Color::new(1.0, 1.0, 1.0)),
+            (0.5, Color::new(0.55, 0.55, 0.55)),
+            (0.0, Color::new(0.1, 0.1, 0.1)),
+        ];
+        for (visibility, expected) in examples {
+            let result = lighting(&m, &o, &light, point, eyev, normalv, visibility);
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn lighting_samples_the_area_light() {
+        let light = Light::from(AreaLight::new(
+            dvec3(-0.5, -0.5, -5.0),
+            dvec3(1.0, 0.0, 0.0), 2,
+            dvec3(0.0, 1.0, 0.0), 2,
+            Color::WHITE
+        ));
+        let m = Material::new()
+            .with_ambient(0.1)
+            .with_diffuse(0.9)
+            .with_specular(0.0);
+        let o = Object::new_sphere();
+        let eye = dvec3(0.0, 0.0, -5.0);
+        let examples = [
+            (dvec3(0.0, 0.0, -1.0), Color::new(0.9965, 0.9965, 0.9965)),
+            (dvec3(0.0, 0.7071, -0.7071), Color::new(0.62318, 0.62318, 0.62318)),
+        ];
+        for (point, expected) in examples {
+            let eyev = (eye - point).normalize();
+            let normalv = point;
+            let result = lighting(&m, &o, &light, point, eyev, normalv, 1.0);
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn lighting_with_a_directional_light() {
+        let (m, position) = setup();
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(DirectionalLight::new(dvec3(0.0, 0.0, 1.0), Color::WHITE));
+        let result = lighting(&m, &Object::new_sphere(), &light, position, eyev, normalv, 1.0);
+        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn lighting_outside_of_a_spot_light_cone_is_only_ambient() {
+        let (m, position) = setup();
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(SpotLight::new(
+            dvec3(0.0, 0.0, -10.0),
+            dvec3(0.0, 1.0, 0.0),
+            0.1,
+            0.2,
+            Color::WHITE
+        ));
+        let result = lighting(&m, &Object::new_sphere(), &light, position, eyev, normalv, 1.0);
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_a_white_lambertian_pbr_surface_matches_phong_diffuse() {
+        let m = Material::new()
+            .with_pbr(PbrMaterial::new(Color::WHITE, 0.0, 1.0).with_specular(0.0));
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        let result = lighting(&m, &Object::new_sphere(), &light, DVec3::ZERO, eyev, normalv, 1.0);
+        assert_eq!(result, Color::new(1.1, 1.1, 1.1));
+    }
+
+    #[test]
+    fn lighting_a_pbr_surface_uses_its_base_color() {
+        let m = Material::new()
+            .with_ambient(0.0)
+            .with_pbr(PbrMaterial::new(Color::new(1.0, 0.5, 0.0), 0.0, 1.0).with_specular(0.0));
+        let eyev = dvec3(0.0, 0.0, -1.0);
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        let result = lighting(&m, &Object::new_sphere(), &light, DVec3::ZERO, eyev, normalv, 1.0);
+        assert_eq!(result, Color::new(1.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn a_pbr_metal_has_a_specular_highlight() {
+        let m = Material::new()
+            .with_ambient(0.0)
+            .with_pbr(PbrMaterial::new(Color::WHITE, 1.0, 0.2));
+        let normalv = dvec3(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(dvec3(0.0, 10.0, -10.0), Color::WHITE));
+        let o = Object::new_sphere();
+        let mirror = dvec3(0.0, -2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0);
+        let off = dvec3(0.0, 0.0, -1.0);
+        let highlight = lighting(&m, &o, &light, DVec3::ZERO, mirror, normalv, 1.0);
+        let elsewhere = lighting(&m, &o, &light, DVec3::ZERO, off, normalv, 1.0);
+        assert!(highlight.r > 1.0);
+        assert!(elsewhere.r < 0.1);
+    }
+}
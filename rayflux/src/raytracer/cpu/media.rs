@@ -0,0 +1,230 @@
+use glam::{DVec2, DVec3};
+
+use crate::{common::{color::Color, Object, Rng, Scene}, raytracer::cpu::{intersections::Intersections, CpuRaytracer}};
+
+/// An interaction of a ray with a medium
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MediumEvent {
+    /// Distance along the ray
+    pub t: f64,
+    /// Fraction of the light scattered by the medium at this point
+    pub albedo: Color,
+}
+
+/// Part of a ray going through a constant set of media
+struct Segment {
+    start: f64,
+    end: f64,
+    density: f64,
+    albedo: Color,
+}
+
+/// Split a ray into segments of constant density, up to `t_max`.
+/// Entering and leaving the objects filled with a medium is tracked by walking the sorted intersections,
+/// the ones behind the origin telling which media the ray starts in.
+fn segments(intersections: &Intersections, t_max: f64, scene: &Scene) -> Vec<Segment> {
+    let mut containers: Vec<&Object> = Vec::new();
+    let mut segments = Vec::new();
+    let mut start: f64 = 0.0;
+
+    for intersection in intersections.get_all().iter() {
+        let object = intersection.object();
+        if object.medium().is_none() {
+            continue;
+        }
+
+        let t = intersection.t();
+        if t > start {
+            segments.extend(segment(&containers, scene, start, t.min(t_max)));
+            if t >= t_max {
+                return segments;
+            }
+            start = t;
+        }
+
+        match containers.iter().position(|container| std::ptr::eq(*container, object)) {
+            Some(position) => { containers.remove(position); },
+            None => containers.push(object),
+        }
+    }
+
+    segments.extend(segment(&containers, scene, start, t_max));
+    segments
+}
+
+/// Combine the media of the containing objects and of the scene, None if there is nothing to go through
+fn segment(containers: &[&Object], scene: &Scene, start: f64, end: f64) -> Option<Segment> {
+    let media = containers
+        .iter()
+        .filter_map(|object| object.medium())
+        .chain(scene.medium());
+
+    let mut density = 0.0;
+    let mut albedo = Color::BLACK;
+    for medium in media {
+        density += medium.density();
+        albedo += medium.albedo() * medium.density();
+    }
+
+    if density > 0.0 && end > start {
+        Some(Segment { start, end, density, albedo: albedo / density })
+    } else {
+        None
+    }
+}
+
+/// Sample the distance at which a ray interacts with the media it goes through before reaching `t_max`,
+/// or None when it goes through unaffected
+pub fn sample_free_flight(intersections: &Intersections, t_max: f64, scene: &Scene, rng: &mut Rng) -> Option<MediumEvent> {
+    for segment in segments(intersections, t_max, scene) {
+        // Distances between interactions are exponentially distributed in a homogeneous medium
+        let distance = -(1.0 - rng.next_f64()).ln() / segment.density;
+        if segment.start + distance < segment.end {
+            return Some(MediumEvent { t: segment.start + distance, albedo: segment.albedo });
+        }
+    }
+    None
+}
+
+/// Fraction of the light going through the media along a ray, up to `t_max`
+pub fn transmittance(intersections: &Intersections, t_max: f64, scene: &Scene) -> f64 {
+    let optical_depth: f64 = segments(intersections, t_max, scene)
+        .iter()
+        .map(|segment| segment.density * (segment.end - segment.start))
+        .sum();
+    (-optical_depth).exp()
+}
+
+/// Light from the lights scattered towards the ray at a point inside a medium.
+/// The medium albedo is not included.
+pub fn in_scattering(scene: &Scene, point: DVec3, rng: &mut Rng) -> Color {
+    let mut sum = Color::BLACK;
+    for light in scene.lights().iter() {
+        let visibility = CpuRaytracer::light_visibility(scene, point, light, rng);
+        if visibility == 0.0 {
+            continue;
+        }
+
+        let samples = light.sample_count();
+        let mut intensity = Color::BLACK;
+        for index in 0..samples {
+            intensity += light.sample(point, index, DVec2::splat(0.5)).intensity;
+        }
+
+        // Isotropic phase function of 1 / 4pi, light intensities being scaled by pi like for surfaces
+        sum += intensity / samples as f64 * (visibility / 4.0);
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use crate::{common::{Medium, PointLight, Transform}, raytracer::cpu::{intersections::{intersect_scene, StandardHit}, Ray}};
+
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    fn fog_sphere(density: f64) -> Scene {
+        let mut scene = Scene::default();
+        scene.add_object(Object::new_sphere().with_medium(Medium::new(density)));
+        scene
+    }
+
+    #[test]
+    fn medium_boundaries_are_not_surfaces() {
+        let scene = fog_sphere(1.0);
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        assert_eq!(xs.count(), 2);
+        assert_eq!(xs.hit(StandardHit {}), None);
+    }
+
+    #[test]
+    fn the_transmittance_through_a_medium_filled_sphere() {
+        let scene = fog_sphere(1.0);
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        assert!((transmittance(&xs, f64::INFINITY, &scene) - (-2.0_f64).exp()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn the_transmittance_from_inside_a_medium() {
+        let scene = fog_sphere(1.0);
+        let r = Ray::new(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        assert!((transmittance(&xs, f64::INFINITY, &scene) - (-1.0_f64).exp()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn the_transmittance_stops_at_the_given_distance() {
+        let scene = fog_sphere(1.0);
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        assert!((transmittance(&xs, 4.5, &scene) - (-0.5_f64).exp()).abs() < EPSILON);
+        assert_eq!(transmittance(&xs, 3.0, &scene), 1.0);
+    }
+
+    #[test]
+    fn overlapping_media_add_up() {
+        let mut scene = fog_sphere(1.0);
+        scene.add_object(
+            Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(0.0, 0.0, 1.0)))
+            .with_medium(Medium::new(2.0))
+        );
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        // 1 alone, 1 with both, 1 with the second one alone
+        let expected = (-(1.0 + 3.0 + 2.0_f64)).exp();
+        assert!((transmittance(&xs, f64::INFINITY, &scene) - expected).abs() < EPSILON);
+    }
+
+    #[test]
+    fn a_scene_medium_fills_everything() {
+        let mut scene = Scene::default();
+        scene.set_medium(Some(Medium::new(0.5)));
+        let r = Ray::new(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        assert!((transmittance(&xs, 3.0, &scene) - (-1.5_f64).exp()).abs() < EPSILON);
+        assert!(sample_free_flight(&xs, f64::INFINITY, &scene, &mut Rng::default()).is_some());
+    }
+
+    #[test]
+    fn free_flights_follow_the_transmittance() {
+        let scene = fog_sphere(1.0);
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        let mut rng = Rng::new(5);
+        let count = 100_000;
+        let mut scattered = 0;
+        for _ in 0..count {
+            if let Some(event) = sample_free_flight(&xs, f64::INFINITY, &scene, &mut rng) {
+                assert!(event.t >= 4.0 && event.t <= 6.0);
+                scattered += 1;
+            }
+        }
+        let expected = 1.0 - (-2.0_f64).exp();
+        assert!((scattered as f64 / count as f64 - expected).abs() < 0.01);
+    }
+
+    #[test]
+    fn free_flights_use_the_albedo_of_the_medium() {
+        let mut scene = Scene::default();
+        scene.set_medium(Some(Medium::new(1.0).with_albedo(Color::RED)));
+        let r = Ray::new(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        let event = sample_free_flight(&xs, f64::INFINITY, &scene, &mut Rng::default()).unwrap();
+        assert_eq!(event.albedo, Color::RED);
+    }
+
+    #[test]
+    fn light_scattered_inside_a_medium() {
+        let mut scene = fog_sphere(0.0);
+        scene.add_light(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        let c = in_scattering(&scene, DVec3::ZERO, &mut Rng::default());
+        assert_eq!(c, Color::new(0.25, 0.25, 0.25));
+    }
+}
@@ -1,21 +1,54 @@
-use crate::{common::{color::Color, Camera, Scene}, raytracer::{cpu::intersections::{intersect_scene, IntersectionInfos, StandardHit}, RaytracerImpl, RaytracerOutput}};
+use crate::{common::{color::Color, Camera, Light, LightSample, Rng, Scene}, raytracer::{cpu::{intersections::{closest_hit, intersect_scene, shading_intersections, IntersectionInfos, ShadowHit, StandardHit}, lighting::lighting, media::{in_scattering, sample_free_flight, transmittance}, path_tracer::path_trace}, RaytracerImpl, RaytracerOutput}};
 
 pub mod canvas;
 pub mod ray;
 pub mod shapes;
 pub mod intersections;
+pub mod lighting;
+pub mod patterns;
+pub mod environment;
+pub mod sampling;
+pub mod brdf;
+pub mod path_tracer;
+pub mod bump_map;
+pub mod media;
+pub mod noise;
+pub mod bounding_box;
+pub mod bvh;
+pub mod polynomial;
 
 pub use canvas::*;
-use glam::UVec2;
+use glam::{DVec2, DVec3, UVec2};
 pub use ray::*;
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
 
+/// Offset used to move secondary rays origins away from the surface they start on
+pub const EPSILON: f64 = 1.0e-5;
+
+/// Default number of bounces a ray can do before stopping the recursion
+pub const DEFAULT_MAX_DEPTH: u32 = 5;
+
+/// Algorithm used to compute the light reaching the camera
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Integrator {
+    /// Recursive reflections and refractions, lights being the only source of illumination
+    #[default]
+    Whitted,
+    /// Monte Carlo path tracing, where emissive objects and inter-reflections light the scene too
+    PathTracing {
+        /// Number of paths averaged per pixel
+        samples: u32,
+    },
+}
+
 pub struct CpuRaytracer {
     canvas: Canvas,
+    max_depth: u32,
+    integrator: Integrator,
 }
 
 impl RaytracerImpl for CpuRaytracer {
-    fn output(&self) -> RaytracerOutput {
+    fn output(&self) -> RaytracerOutput<'_> {
         RaytracerOutput::Image
     }
 }
@@ -24,12 +57,43 @@ impl CpuRaytracer {
     pub fn new(size: UVec2) -> Self {
         Self {
             canvas: Canvas::new(size.x, size.y),
+            max_depth: DEFAULT_MAX_DEPTH,
+            integrator: Integrator::default(),
         }
     }
 
+    /// Set the maximum number of recursive rays (reflections) traced from a camera ray
+    pub fn with_max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: u32) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    pub fn with_integrator(mut self, integrator: Integrator) -> Self {
+        self.integrator = integrator;
+        self
+    }
+
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
     /// Render the content of the scene
     pub fn render(&mut self, scene: &Scene) {
         let canvas_size = self.canvas.size();
+        let max_depth = self.max_depth;
+        let integrator = self.integrator;
 
         self.canvas
             .pixels_mut()
@@ -39,13 +103,34 @@ impl CpuRaytracer {
                 let y = i / canvas_size.x as usize;
                 let x = i - y * canvas_size.x as usize;
 
-                let ray = Self::ray_for_pixel(
-                    scene.camera(), 
-                    x as f32, 
-                    y as f32, 
-                    canvas_size
-                );
-                *color += Self::raytrace(&ray, scene).unwrap_or(scene.camera().background())
+                // Seeded with the pixel index so that renders are reproducible
+                let mut rng = Rng::new(i as u64);
+
+                match integrator {
+                    Integrator::Whitted => {
+                        let ray = Self::ray_for_pixel(
+                            scene.camera(), 
+                            x as f32, 
+                            y as f32, 
+                            canvas_size
+                        );
+                        *color += Self::color_at(&ray, scene, max_depth, &mut rng)
+                    },
+                    Integrator::PathTracing { samples } => {
+                        // Every path goes through a random position inside the pixel to antialias edges
+                        let mut sum = Color::BLACK;
+                        for _ in 0..samples {
+                            let ray = Self::ray_for_pixel(
+                                scene.camera(),
+                                x as f32 + rng.next_f64() as f32 - 0.5,
+                                y as f32 + rng.next_f64() as f32 - 0.5,
+                                canvas_size
+                            );
+                            sum += path_trace(&ray, scene, max_depth, &mut rng);
+                        }
+                        *color += sum / samples.max(1) as f64
+                    },
+                }
             });
     }
 
@@ -78,20 +163,622 @@ impl CpuRaytracer {
         Ray::new(eye.as_dvec3(), direction.as_dvec3())
     }
 
-    /// Trace a ray through the scene and return the resulting color or None if no hit happened
-    fn raytrace(&ray: &Ray, scene: &Scene) -> Option<Color> {
-        let intersections = intersect_scene(&ray, &scene);
-        match intersections.hit_index(StandardHit {}) {
+    /// Trace a ray through the scene and return the resulting color or None if no hit happened.
+    /// `remaining` is the number of recursive rays that can still be spawned from this one.
+    fn raytrace(ray: &Ray, scene: &Scene, remaining: u32, rng: &mut Rng) -> Option<Color> {
+        let intersections = shading_intersections(ray, scene);
+        let hit = intersections.hit_index(StandardHit {});
+
+        // The ray may be scattered by a medium before reaching the surface, only the direct light is gathered there
+        let t_max = hit.map_or(f64::INFINITY, |index| intersections[index].t());
+        if let Some(event) = sample_free_flight(&intersections, t_max, scene, rng) {
+            return Some(in_scattering(scene, ray.at(event.t), rng) * event.albedo);
+        }
+
+        match hit {
             Some(index) => {
-                let infos = IntersectionInfos::new(&intersections, index, &ray);
-                Some(Self::shade_hit(&infos))
+                let infos = IntersectionInfos::new(&intersections, index, ray);
+                Some(Self::shade_hit(scene, &infos, remaining, rng))
             },
             None => None
         }
     }
 
-    fn shade_hit(infos: &IntersectionInfos) -> Color {
-        let normal = infos.normal;
-        Color::new(normal.x, normal.y, normal.z)
+    /// Trace a ray through the scene and return the resulting color or the background if no hit happened
+    fn color_at(ray: &Ray, scene: &Scene, remaining: u32, rng: &mut Rng) -> Color {
+        Self::raytrace(ray, scene, remaining, rng).unwrap_or_else(|| Self::background(ray, scene))
+    }
+
+    /// Color seen by a ray missing every object
+    fn background(ray: &Ray, scene: &Scene) -> Color {
+        match scene.environment() {
+            Some(environment) => environment.color_at(ray.direction),
+            None => scene.camera().background(),
+        }
+    }
+
+    /// Compute the color at an intersection by adding the contribution of every light to the light emitted by the surface
+    fn shade_hit(scene: &Scene, infos: &IntersectionInfos, remaining: u32, rng: &mut Rng) -> Color {
+        let material = infos.object.material();
+        let mut surface = material.emitted();
+        for light in scene.lights().iter() {
+            let visibility = Self::light_visibility(scene, infos.over_point, light, rng);
+            surface += lighting(material, infos.object, light, infos.over_point, infos.eyev, infos.normal, visibility);
+        }
+
+        let reflected = Self::reflected_color(scene, infos, remaining, rng);
+        let refracted = Self::refracted_color(scene, infos, remaining, rng);
+
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = infos.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
+
+    /// Compute the color seen along the reflected direction, scaled by the material reflectivity
+    fn reflected_color(scene: &Scene, infos: &IntersectionInfos, remaining: u32, rng: &mut Rng) -> Color {
+        let reflective = infos.object.material().reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return Color::BLACK;
+        }
+
+        let reflect_ray = Ray::new(infos.over_point, infos.reflectv);
+        Self::color_at(&reflect_ray, scene, remaining - 1, rng) * reflective
+    }
+
+    /// Compute the color seen through the surface, scaled by the material transparency
+    fn refracted_color(scene: &Scene, infos: &IntersectionInfos, remaining: u32, rng: &mut Rng) -> Color {
+        let transparency = infos.object.material().transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::BLACK;
+        }
+
+        match infos.refractv() {
+            Some(direction) => {
+                let refract_ray = Ray::new(infos.under_point, direction);
+                Self::color_at(&refract_ray, scene, remaining - 1, rng) * transparency
+            },
+            // Total internal reflection
+            None => Color::BLACK,
+        }
+    }
+
+    /// Fraction of a light reaching a point, averaging jittered shadow rays across area lights
+    /// and attenuating them through the media they cross
+    fn light_visibility(scene: &Scene, point: DVec3, light: &Light, rng: &mut Rng) -> f64 {
+        let samples = light.sample_count();
+        let has_media = scene.has_media();
+        let mut visible = 0.0;
+        for index in 0..samples {
+            let offset = if light.jitter() {
+                DVec2::new(rng.next_f64(), rng.next_f64())
+            } else {
+                DVec2::splat(0.5)
+            };
+            let sample = light.sample(point, index, offset);
+            if !Self::is_shadowed(scene, point, &sample) {
+                visible += if has_media {
+                    let ray = Ray::new(point, sample.direction);
+                    transmittance(&intersect_scene(&ray, scene), sample.distance, scene)
+                } else {
+                    1.0
+                };
+            }
+        }
+        visible / samples as f64
+    }
+
+    /// Check if an object lies between a point and a light sample
+    fn is_shadowed(scene: &Scene, point: DVec3, sample: &LightSample) -> bool {
+        let ray = Ray::new(point, sample.direction);
+        closest_hit(&ray, scene, ShadowHit {}, sample.distance).is_some()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use crate::common::{AreaLight, DirectionalLight, EnvironmentMap, ImageTexture, Material, Medium, Object, PointLight, Transform};
+
+    use super::*;
+
+    pub fn default_scene() -> Scene {
+        let mut scene = Scene::default();
+        scene.add_light(
+            PointLight::new(dvec3(-10.0, 10.0, -10.0), Color::WHITE)
+        );
+        scene.add_object(
+            Object::new_sphere()
+            .with_material(
+                Material::new()
+                .with_color(Color::new(0.8, 1.0, 0.6))
+                .with_diffuse(0.7)
+                .with_specular(0.2)
+            )
+        );
+        scene.add_object(
+            Object::new_sphere()
+            .with_transform(
+                Transform::from_scale(dvec3(0.5, 0.5, 0.5))
+            )
+        );
+        scene
+    }
+
+    #[test]
+    fn shading_an_intersection() {
+        let scene = default_scene();
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        let index = xs.hit_index(StandardHit {}).unwrap();
+        let infos = IntersectionInfos::new(&xs, index, &r);
+        let c = CpuRaytracer::shade_hit(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn shading_an_intersection_from_the_inside() {
+        let mut scene = default_scene();
+        scene.lights_mut()[0] = PointLight::new(dvec3(0.0, 0.25, 0.0), Color::WHITE).into();
+        let r = Ray::new(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        let index = xs.hit_index(StandardHit {}).unwrap();
+        let infos = IntersectionInfos::new(&xs, index, &r);
+        assert!(infos.inside);
+        let c = CpuRaytracer::shade_hit(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_nothing_is_collinear_with_point_and_light() {
+        let scene = default_scene();
+        let p = dvec3(0.0, 10.0, 0.0);
+        let sample = scene.lights()[0].sample(p, 0, DVec2::splat(0.5));
+        assert!(!CpuRaytracer::is_shadowed(&scene, p, &sample));
+    }
+
+    #[test]
+    fn the_shadow_when_an_object_is_between_the_point_and_the_light() {
+        let scene = default_scene();
+        let p = dvec3(10.0, -10.0, 10.0);
+        let sample = scene.lights()[0].sample(p, 0, DVec2::splat(0.5));
+        assert!(CpuRaytracer::is_shadowed(&scene, p, &sample));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_an_object_is_behind_the_light() {
+        let scene = default_scene();
+        let p = dvec3(-20.0, 20.0, -20.0);
+        let sample = scene.lights()[0].sample(p, 0, DVec2::splat(0.5));
+        assert!(!CpuRaytracer::is_shadowed(&scene, p, &sample));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_an_object_is_behind_the_point() {
+        let scene = default_scene();
+        let p = dvec3(-2.0, 2.0, -2.0);
+        let sample = scene.lights()[0].sample(p, 0, DVec2::splat(0.5));
+        assert!(!CpuRaytracer::is_shadowed(&scene, p, &sample));
+    }
+
+    #[test]
+    fn there_is_no_shadow_when_the_object_does_not_cast_shadows() {
+        let mut scene = default_scene();
+        for object in scene.objects_mut().iter_mut() {
+            object.set_shadow(false);
+        }
+        let p = dvec3(10.0, -10.0, 10.0);
+        let sample = scene.lights()[0].sample(p, 0, DVec2::splat(0.5));
+        assert!(!CpuRaytracer::is_shadowed(&scene, p, &sample));
+    }
+
+    #[test]
+    fn shade_hit_is_given_an_intersection_in_shadow() {
+        let mut scene = Scene::default();
+        scene.add_light(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        scene.add_object(Object::new_sphere());
+        scene.add_object(
+            Object::new_sphere()
+            .with_transform(
+                Transform::from_translation(dvec3(0.0, 0.0, 10.0))
+            )
+        );
+        let r = Ray::new(dvec3(0.0, 0.0, 5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        let index = xs.hit_index(StandardHit {}).unwrap();
+        let infos = IntersectionInfos::new(&xs, index, &r);
+        let c = CpuRaytracer::shade_hit(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn the_color_when_a_ray_misses() {
+        let scene = default_scene();
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 1.0, 0.0));
+        assert_eq!(CpuRaytracer::raytrace(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default()), None);
+    }
+
+    #[test]
+    fn the_color_when_a_ray_hits() {
+        let scene = default_scene();
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(CpuRaytracer::raytrace(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default()), Some(Color::new(0.38066, 0.47583, 0.2855)));
+    }
+
+    #[test]
+    fn the_reflected_color_for_a_nonreflective_material() {
+        let mut scene = default_scene();
+        scene.objects_mut()[1].material_mut().ambient = 1.0;
+        let r = Ray::new(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        let index = xs.hit_index(StandardHit {}).unwrap();
+        let infos = IntersectionInfos::new(&xs, index, &r);
+        let c = CpuRaytracer::reflected_color(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::BLACK);
+    }
+
+    fn scene_with_reflective_plane(reflective: f64) -> Scene {
+        let mut scene = default_scene();
+        scene.add_object(
+            Object::new_plane()
+            .with_material(
+                Material::new()
+                .with_reflective(reflective)
+            )
+            .with_transform(
+                Transform::from_translation(dvec3(0.0, -1.0, 0.0))
+            )
+        );
+        scene
+    }
+
+    #[test]
+    fn the_reflected_color_for_a_reflective_material() {
+        let scene = scene_with_reflective_plane(0.5);
+        let r = Ray::new(dvec3(0.0, 0.0, -3.0), dvec3(0.0, -2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0));
+        let xs = intersect_scene(&r, &scene);
+        let index = xs.hit_index(StandardHit {}).unwrap();
+        let infos = IntersectionInfos::new(&xs, index, &r);
+        let c = CpuRaytracer::reflected_color(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::new(0.19032, 0.2379, 0.14274));
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_material() {
+        let scene = scene_with_reflective_plane(0.5);
+        let r = Ray::new(dvec3(0.0, 0.0, -3.0), dvec3(0.0, -2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0));
+        let xs = intersect_scene(&r, &scene);
+        let index = xs.hit_index(StandardHit {}).unwrap();
+        let infos = IntersectionInfos::new(&xs, index, &r);
+        let c = CpuRaytracer::shade_hit(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::new(0.87677, 0.92436, 0.82918));
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces() {
+        let mut scene = Scene::default();
+        scene.add_light(PointLight::new(dvec3(0.0, 0.0, 0.0), Color::WHITE));
+        scene.add_object(
+            Object::new_plane()
+            .with_material(Material::new().with_reflective(1.0))
+            .with_transform(Transform::from_translation(dvec3(0.0, -1.0, 0.0)))
+        );
+        scene.add_object(
+            Object::new_plane()
+            .with_material(Material::new().with_reflective(1.0))
+            .with_transform(Transform::from_translation(dvec3(0.0, 1.0, 0.0)))
+        );
+        let r = Ray::new(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 1.0, 0.0));
+        // Must terminate
+        let _ = CpuRaytracer::color_at(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default());
+    }
+
+    #[test]
+    fn the_reflected_color_at_the_maximum_recursive_depth() {
+        let scene = scene_with_reflective_plane(0.5);
+        let r = Ray::new(dvec3(0.0, 0.0, -3.0), dvec3(0.0, -2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0));
+        let xs = intersect_scene(&r, &scene);
+        let index = xs.hit_index(StandardHit {}).unwrap();
+        let infos = IntersectionInfos::new(&xs, index, &r);
+        let c = CpuRaytracer::reflected_color(&scene, &infos, 0, &mut Rng::default());
+        assert_eq!(c, Color::BLACK);
+    }
+
+    #[test]
+    fn the_refracted_color_with_an_opaque_surface() {
+        let scene = default_scene();
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        let infos = IntersectionInfos::new(&xs, 0, &r);
+        let c = CpuRaytracer::refracted_color(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::BLACK);
+    }
+
+    #[test]
+    fn the_refracted_color_at_the_maximum_recursive_depth() {
+        let mut scene = default_scene();
+        let material = scene.objects_mut()[0].material_mut();
+        material.transparency = 1.0;
+        material.refractive_index = 1.5;
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = intersect_scene(&r, &scene);
+        let infos = IntersectionInfos::new(&xs, 0, &r);
+        let c = CpuRaytracer::refracted_color(&scene, &infos, 0, &mut Rng::default());
+        assert_eq!(c, Color::BLACK);
+    }
+
+    #[test]
+    fn the_refracted_color_under_total_internal_reflection() {
+        let mut scene = default_scene();
+        let material = scene.objects_mut()[0].material_mut();
+        material.transparency = 1.0;
+        material.refractive_index = 1.5;
+        let r = Ray::new(dvec3(0.0, 0.0, 2.0_f64.sqrt() / 2.0), dvec3(0.0, 1.0, 0.0));
+        let xs = intersect_scene(&r, &scene);
+        // Inside the sphere, so look at the second intersection
+        let infos = IntersectionInfos::new(&xs, 1, &r);
+        let c = CpuRaytracer::refracted_color(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::BLACK);
+    }
+
+    #[test]
+    fn shade_hit_with_a_transparent_material() {
+        let mut scene = default_scene();
+        scene.add_object(
+            Object::new_plane()
+            .with_material(
+                Material::new()
+                .with_transparency(0.5)
+                .with_refractive_index(1.5)
+            )
+            .with_transform(Transform::from_translation(dvec3(0.0, -1.0, 0.0)))
+        );
+        scene.add_object(
+            Object::new_sphere()
+            .with_material(
+                Material::new()
+                .with_color(Color::RED)
+                .with_ambient(0.5)
+            )
+            .with_transform(Transform::from_translation(dvec3(0.0, -3.5, -0.5)))
+        );
+        let r = Ray::new(dvec3(0.0, 0.0, -3.0), dvec3(0.0, -2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0));
+        let xs = intersect_scene(&r, &scene);
+        let index = xs.hit_index(StandardHit {}).unwrap();
+        let infos = IntersectionInfos::new(&xs, index, &r);
+        let c = CpuRaytracer::shade_hit(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::new(0.93642, 0.68642, 0.68642));
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_transparent_material() {
+        let mut scene = default_scene();
+        scene.add_object(
+            Object::new_plane()
+            .with_material(
+                Material::new()
+                .with_reflective(0.5)
+                .with_transparency(0.5)
+                .with_refractive_index(1.5)
+            )
+            .with_transform(Transform::from_translation(dvec3(0.0, -1.0, 0.0)))
+        );
+        scene.add_object(
+            Object::new_sphere()
+            .with_material(
+                Material::new()
+                .with_color(Color::RED)
+                .with_ambient(0.5)
+            )
+            .with_transform(Transform::from_translation(dvec3(0.0, -3.5, -0.5)))
+        );
+        let r = Ray::new(dvec3(0.0, 0.0, -3.0), dvec3(0.0, -2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0));
+        let xs = intersect_scene(&r, &scene);
+        let index = xs.hit_index(StandardHit {}).unwrap();
+        let infos = IntersectionInfos::new(&xs, index, &r);
+        let c = CpuRaytracer::shade_hit(&scene, &infos, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::new(0.93391, 0.69643, 0.69243));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn point_lights_evaluate_the_light_visibility() {
+        let scene = default_scene();
+        let light = scene.lights()[0];
+        let examples = [
+            (dvec3(0.0, 1.0001, 0.0), 1.0),
+            (dvec3(-1.0001, 0.0, 0.0), 1.0),
+            (dvec3(0.0, 0.0, -1.0001), 1.0),
+            (dvec3(0.0, 0.0, 1.0001), 0.0),
+            (dvec3(1.0001, 0.0, 0.0), 0.0),
+            (dvec3(0.0, -1.0001, 0.0), 0.0),
+            (dvec3(0.0, 0.0, 0.0), 0.0),
+        ];
+        for (point, expected) in examples {
+            let visibility = CpuRaytracer::light_visibility(&scene, point, &light, &mut Rng::default());
+            assert_eq!(visibility, expected);
+        }
+    }
+
+    #[test]
+    fn area_lights_evaluate_the_light_visibility() {
+        let scene = default_scene();
+        let light = Light::from(
+            AreaLight::new(
+                dvec3(-0.5, -0.5, -5.0),
+                dvec3(1.0, 0.0, 0.0), 2,
+                dvec3(0.0, 1.0, 0.0), 2,
+                Color::WHITE
+            )
+            .with_jitter(false)
+        );
+        let examples = [
+            (dvec3(0.0, 0.0, 2.0), 0.0),
+            (dvec3(1.0, -1.0, 2.0), 0.25),
+            (dvec3(1.5, 0.0, 2.0), 0.5),
+            (dvec3(1.25, 1.25, 3.0), 0.75),
+            (dvec3(0.0, 0.0, -2.0), 1.0),
+        ];
+        for (point, expected) in examples {
+            let visibility = CpuRaytracer::light_visibility(&scene, point, &light, &mut Rng::default());
+            assert_eq!(visibility, expected);
+        }
+    }
+
+    #[test]
+    fn jittered_area_lights_are_reproducible() {
+        let scene = default_scene();
+        let light = Light::from(
+            AreaLight::new(
+                dvec3(-0.5, -0.5, -5.0),
+                dvec3(1.0, 0.0, 0.0), 4,
+                dvec3(0.0, 1.0, 0.0), 4,
+                Color::WHITE
+            )
+        );
+        let point = dvec3(1.25, 0.25, 2.5);
+        let a = CpuRaytracer::light_visibility(&scene, point, &light, &mut Rng::new(3));
+        let b = CpuRaytracer::light_visibility(&scene, point, &light, &mut Rng::new(3));
+        assert_eq!(a, b);
+        assert!(a > 0.0 && a < 1.0);
+    }
+
+    #[test]
+    fn directional_lights_are_shadowed_at_any_distance() {
+        let scene = default_scene();
+        let light = Light::from(DirectionalLight::new(dvec3(0.0, -1.0, 0.0), Color::WHITE));
+        let below = dvec3(0.0, -1000.0, 0.0);
+        let aside = dvec3(2.0, -1000.0, 0.0);
+        assert_eq!(CpuRaytracer::light_visibility(&scene, below, &light, &mut Rng::default()), 0.0);
+        assert_eq!(CpuRaytracer::light_visibility(&scene, aside, &light, &mut Rng::default()), 1.0);
+    }
+
+    fn sky_environment() -> EnvironmentMap {
+        // Blue sky above, green ground below
+        EnvironmentMap::new(
+            ImageTexture::new(1, 2, vec![Color::BLUE, Color::GREEN])
+        )
+    }
+
+    #[test]
+    fn a_missing_ray_sees_the_environment() {
+        let mut scene = default_scene();
+        scene.set_environment(Some(sky_environment()));
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 1.0, 0.0));
+        let c = CpuRaytracer::color_at(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::BLUE);
+    }
+
+    #[test]
+    fn reflections_see_the_environment() {
+        let mut scene = Scene::default();
+        scene.set_environment(Some(sky_environment()));
+        scene.add_object(
+            Object::new_plane()
+            .with_material(
+                Material::new()
+                .with_ambient(0.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0)
+                .with_reflective(1.0)
+            )
+        );
+        let r = Ray::new(dvec3(0.0, 1.0, -1.0), dvec3(0.0, -1.0, 1.0).normalize());
+        let c = CpuRaytracer::color_at(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::BLUE);
+    }
+
+    #[test]
+    fn refractions_see_the_environment() {
+        let mut scene = Scene::default();
+        scene.set_environment(Some(sky_environment()));
+        scene.add_object(
+            Object::new_plane()
+            .with_material(
+                Material::new()
+                .with_ambient(0.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0)
+                .with_transparency(1.0)
+            )
+        );
+        let r = Ray::new(dvec3(0.0, 1.0, -1.0), dvec3(0.0, -1.0, 1.0).normalize());
+        let c = CpuRaytracer::color_at(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::GREEN);
+    }
+
+    #[test]
+    fn an_emissive_object_glows_without_lights() {
+        let mut scene = Scene::default();
+        scene.add_object(
+            Object::new_sphere()
+            .with_material(Material::new().with_emission(Color::new(1.0, 0.5, 0.0), 2.0))
+        );
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let c = CpuRaytracer::color_at(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::new(2.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn reflections_see_emissive_objects() {
+        let mut scene = Scene::default();
+        scene.add_object(
+            Object::new_plane()
+            .with_material(
+                Material::new()
+                .with_ambient(0.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0)
+                .with_reflective(1.0)
+            )
+        );
+        scene.add_object(
+            Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(0.0, 3.0, 2.0)))
+            .with_material(Material::new().with_emission(Color::RED, 1.0))
+        );
+        let r = Ray::new(dvec3(0.0, 1.0, -1.0), dvec3(0.0, -1.0, 1.0).normalize());
+        let c = CpuRaytracer::color_at(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c, Color::RED);
+    }
+
+    #[test]
+    fn rendering_with_path_tracing_is_reproducible() {
+        let mut scene = default_scene();
+        scene.camera_mut().update_aspect_ratio(UVec2::new(4, 4));
+        let integrator = Integrator::PathTracing { samples: 2 };
+        let mut a = CpuRaytracer::new(UVec2::new(4, 4)).with_integrator(integrator);
+        let mut b = CpuRaytracer::new(UVec2::new(4, 4)).with_integrator(integrator);
+        a.render(&scene);
+        b.render(&scene);
+        for y in 0..4 {
+            assert_eq!(a.canvas()[y], b.canvas()[y]);
+        }
+    }
+
+    #[test]
+    fn media_attenuate_the_light_visibility() {
+        let mut scene = Scene::default();
+        let light = Light::from(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        scene.add_object(
+            Object::new_cube()
+            .with_transform(Transform::from_translation(dvec3(0.0, 0.0, -5.0)))
+            .with_medium(Medium::new(0.5))
+        );
+        let visibility = CpuRaytracer::light_visibility(&scene, DVec3::ZERO, &light, &mut Rng::default());
+        assert!((visibility - (-1.0_f64).exp()).abs() < 0.00001);
+    }
+
+    #[test]
+    fn a_ray_scattered_by_fog_gathers_the_light_at_that_point() {
+        let mut scene = Scene::default();
+        scene.add_light(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        scene.set_medium(Some(Medium::new(1.0).with_albedo(Color::new(0.0, 0.0, 1.0))));
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let c = CpuRaytracer::color_at(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default());
+        assert_eq!(c.r, 0.0);
+        assert_eq!(c.g, 0.0);
+        assert!(c.b > 0.0);
+    }
+}
@@ -0,0 +1,263 @@
+use std::sync::OnceLock;
+
+use glam::DVec3;
+
+use crate::common::{Noise, Rng};
+
+/// Seed of the shuffled lattice used by the gradient noise
+const PERMUTATION_SEED: u64 = 0x5eed;
+
+/// Offsets decorrelating the three components of a displacement
+const DISPLACEMENT_OFFSETS: [DVec3; 3] = [
+    DVec3::new(5.2, 1.3, 7.1),
+    DVec3::new(1.7, 9.2, 3.4),
+    DVec3::new(8.3, 2.8, 5.5),
+];
+
+impl Noise {
+    /// Value of the noise at a point, between 0 and 1
+    pub fn value(&self, point: DVec3) -> f64 {
+        let value = match *self {
+            Noise::Perlin => 0.5 + 0.5 * perlin(point),
+            Noise::Fbm { octaves, lacunarity, gain } => 0.5 + 0.5 * fbm(point, octaves, lacunarity, gain),
+            Noise::Turbulence { octaves, lacunarity, gain } => turbulence(point, octaves, lacunarity, gain),
+            Noise::Worley => worley(point),
+        };
+        value.clamp(0.0, 1.0)
+    }
+
+    /// A vector with components between -1 and 1, each one following the noise
+    pub fn displacement(&self, point: DVec3) -> DVec3 {
+        DVec3::new(
+            self.value(point + DISPLACEMENT_OFFSETS[0]),
+            self.value(point + DISPLACEMENT_OFFSETS[1]),
+            self.value(point + DISPLACEMENT_OFFSETS[2]),
+        ) * 2.0 - 1.0
+    }
+}
+
+/// Shuffled indices 0 to 255, repeated twice to avoid wrapping the lookups
+fn permutation() -> &'static [usize; 512] {
+    static PERMUTATION: OnceLock<[usize; 512]> = OnceLock::new();
+    PERMUTATION.get_or_init(|| {
+        let mut values: [usize; 256] = std::array::from_fn(|i| i);
+        let mut rng = Rng::new(PERMUTATION_SEED);
+        for i in (1..values.len()).rev() {
+            let j = rng.next_u32() as usize % (i + 1);
+            values.swap(i, j);
+        }
+        std::array::from_fn(|i| values[i & 255])
+    })
+}
+
+/// Ease curve with zero first and second derivatives at 0 and 1
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// Dot product of the offset with one of the 12 gradients pointing to the edges of a cube
+fn gradient(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+    u + v
+}
+
+/// Ken Perlin's improved gradient noise, between -1 and 1 and zero on every integer point
+pub fn perlin(point: DVec3) -> f64 {
+    let p = permutation();
+
+    let floor = point.floor();
+    let xi = (floor.x as i64 & 255) as usize;
+    let yi = (floor.y as i64 & 255) as usize;
+    let zi = (floor.z as i64 & 255) as usize;
+
+    let x = point.x - floor.x;
+    let y = point.y - floor.y;
+    let z = point.z - floor.z;
+
+    let u = fade(x);
+    let v = fade(y);
+    let w = fade(z);
+
+    // Hash the coordinates of the 8 cube corners
+    let a = p[xi] + yi;
+    let aa = p[a] + zi;
+    let ab = p[a + 1] + zi;
+    let b = p[xi + 1] + yi;
+    let ba = p[b] + zi;
+    let bb = p[b + 1] + zi;
+
+    lerp(w,
+        lerp(v,
+            lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)),
+            lerp(u, gradient(p[ab], x, y - 1.0, z), gradient(p[bb], x - 1.0, y - 1.0, z)),
+        ),
+        lerp(v,
+            lerp(u, gradient(p[aa + 1], x, y, z - 1.0), gradient(p[ba + 1], x - 1.0, y, z - 1.0)),
+            lerp(u, gradient(p[ab + 1], x, y - 1.0, z - 1.0), gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0)),
+        ),
+    )
+}
+
+/// Fractional Brownian motion, normalized to stay between -1 and 1
+pub fn fbm(point: DVec3, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+    octaves_sum(point, octaves, lacunarity, gain, perlin)
+}
+
+/// Sum of absolute Perlin octaves, normalized to stay between 0 and 1
+pub fn turbulence(point: DVec3, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+    octaves_sum(point, octaves, lacunarity, gain, |p| perlin(p).abs())
+}
+
+fn octaves_sum(point: DVec3, octaves: u32, lacunarity: f64, gain: f64, octave: impl Fn(DVec3) -> f64) -> f64 {
+    let mut sum = 0.0;
+    let mut total = 0.0;
+    let mut frequency = 1.0;
+    let mut amplitude = 1.0;
+    for _ in 0..octaves.max(1) {
+        sum += octave(point * frequency) * amplitude;
+        total += amplitude;
+        frequency *= lacunarity;
+        amplitude *= gain;
+    }
+    sum / total
+}
+
+/// Random feature point of a unit cell
+fn feature_point(x: i64, y: i64, z: i64) -> DVec3 {
+    let hash = (x as u64).wrapping_mul(0x9e3779b97f4a7c15)
+        ^ (y as u64).wrapping_mul(0xc2b2ae3d27d4eb4f)
+        ^ (z as u64).wrapping_mul(0x165667b19e3779f9);
+    let mut rng = Rng::new(hash);
+    DVec3::new(
+        x as f64 + rng.next_f64(),
+        y as f64 + rng.next_f64(),
+        z as f64 + rng.next_f64(),
+    )
+}
+
+/// Worley cellular noise, the distance to the nearest feature point
+pub fn worley(point: DVec3) -> f64 {
+    let cell = point.floor();
+    let (cx, cy, cz) = (cell.x as i64, cell.y as i64, cell.z as i64);
+
+    // The nearest feature point is always in one of the neighbouring cells
+    let mut nearest = f64::INFINITY;
+    for x in cx - 1..=cx + 1 {
+        for y in cy - 1..=cy + 1 {
+            for z in cz - 1..=cz + 1 {
+                nearest = nearest.min(feature_point(x, y, z).distance_squared(point));
+            }
+        }
+    }
+    nearest.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use super::*;
+
+    fn sample_points() -> impl Iterator<Item = DVec3> {
+        let mut rng = Rng::new(42);
+        (0..2000).map(move |_| {
+            dvec3(rng.next_f64(), rng.next_f64(), rng.next_f64()) * 20.0 - 10.0
+        })
+    }
+
+    #[test]
+    fn perlin_noise_is_zero_on_the_lattice() {
+        assert_eq!(perlin(dvec3(0.0, 0.0, 0.0)), 0.0);
+        assert_eq!(perlin(dvec3(3.0, -2.0, 7.0)), 0.0);
+    }
+
+    #[test]
+    fn perlin_noise_stays_between_minus_one_and_one() {
+        let mut varies = false;
+        for p in sample_points() {
+            let value = perlin(p);
+            assert!((-1.0..=1.0).contains(&value));
+            varies |= value.abs() > 0.2;
+        }
+        assert!(varies);
+    }
+
+    #[test]
+    fn perlin_noise_is_continuous() {
+        for p in sample_points() {
+            let delta = (perlin(p) - perlin(p + dvec3(0.001, 0.001, 0.001))).abs();
+            assert!(delta < 0.01);
+        }
+    }
+
+    #[test]
+    fn perlin_noise_is_deterministic() {
+        let p = dvec3(1.3, -4.2, 0.7);
+        assert_eq!(perlin(p), perlin(p));
+    }
+
+    #[test]
+    fn fbm_with_a_single_octave_is_perlin_noise() {
+        let p = dvec3(1.3, -4.2, 0.7);
+        assert_eq!(fbm(p, 1, 2.0, 0.5), perlin(p));
+    }
+
+    #[test]
+    fn fbm_adds_finer_details() {
+        let p = dvec3(1.3, -4.2, 0.7);
+        let expected = (perlin(p) + perlin(p * 2.0) * 0.5) / 1.5;
+        assert!((fbm(p, 2, 2.0, 0.5) - expected).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn turbulence_stays_between_zero_and_one() {
+        for p in sample_points() {
+            let value = turbulence(p, 4, 2.0, 0.5);
+            assert!((0.0..=1.0).contains(&value));
+        }
+    }
+
+    #[test]
+    fn worley_noise_is_zero_on_feature_points() {
+        let p = feature_point(2, -1, 3);
+        assert!(worley(p) < 1.0e-12);
+    }
+
+    #[test]
+    fn worley_noise_is_the_distance_to_the_nearest_feature_point() {
+        for p in sample_points().take(100) {
+            let value = worley(p);
+            let cell = p.floor();
+            let own = feature_point(cell.x as i64, cell.y as i64, cell.z as i64);
+            assert!(value >= 0.0);
+            assert!(value <= own.distance(p) + 1.0e-12);
+        }
+    }
+
+    #[test]
+    fn noise_values_stay_between_zero_and_one() {
+        let noises = [Noise::Perlin, Noise::fbm(5), Noise::turbulence(5), Noise::Worley];
+        for noise in noises {
+            for p in sample_points().take(200) {
+                let value = noise.value(p);
+                assert!((0.0..=1.0).contains(&value));
+                let d = noise.displacement(p);
+                assert!(d.abs().max_element() <= 1.0);
+            }
+        }
+    }
+}
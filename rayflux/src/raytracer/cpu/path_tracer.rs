@@ -0,0 +1,205 @@
+use crate::{common::{color::Color, Material, Rng, Scene}, raytracer::cpu::{intersections::{shading_intersections, IntersectionInfos, StandardHit}, lighting::{direct_lighting, surface_color}, media::{in_scattering, sample_free_flight}, sampling::{cosine_sample_hemisphere, uniform_sample_sphere}, CpuRaytracer, Ray}};
+
+/// Estimate the light arriving along a ray by following a single random path through the scene.
+/// Lights are sampled at every bounce, emissive objects contribute when the path runs into them.
+/// `max_depth` is the number of bounces the path can do.
+pub fn path_trace(ray: &Ray, scene: &Scene, max_depth: u32, rng: &mut Rng) -> Color {
+    let mut radiance = Color::BLACK;
+    let mut throughput = Color::WHITE;
+    let mut ray = *ray;
+
+    for bounce in 0..=max_depth {
+        let intersections = shading_intersections(&ray, scene);
+        let hit = intersections.hit_index(StandardHit {});
+
+        // Media scatter the path before it reaches the surface, towards any direction
+        let t_max = hit.map_or(f64::INFINITY, |index| intersections[index].t());
+        if let Some(event) = sample_free_flight(&intersections, t_max, scene, rng) {
+            let point = ray.at(event.t);
+            throughput *= event.albedo;
+            radiance += throughput * in_scattering(scene, point, rng);
+            if bounce == max_depth {
+                break;
+            }
+            ray = Ray::new(point, uniform_sample_sphere(rng));
+            continue;
+        }
+
+        let Some(index) = hit else {
+            radiance += throughput * CpuRaytracer::background(&ray, scene);
+            break;
+        };
+
+        let infos = IntersectionInfos::new(&intersections, index, &ray);
+        let material = infos.object.material();
+        let color = surface_color(material, infos.object, infos.over_point);
+
+        radiance += throughput * material.emitted();
+
+        for light in scene.lights().iter() {
+            let visibility = CpuRaytracer::light_visibility(scene, infos.over_point, light, rng);
+            if visibility > 0.0 {
+                let direct = direct_lighting(material, color, light, infos.over_point, infos.eyev, infos.normal);
+                radiance += throughput * direct * visibility;
+            }
+        }
+
+        if bounce == max_depth {
+            break;
+        }
+
+        match scatter(&infos, material, color, rng) {
+            Some((next, weight)) => {
+                throughput *= weight;
+                ray = next;
+            },
+            None => break,
+        }
+
+        if throughput.r <= 0.0 && throughput.g <= 0.0 && throughput.b <= 0.0 {
+            break;
+        }
+    }
+
+    radiance
+}
+
+/// Choose how the path continues after a hit, splitting between diffuse, reflected and refracted light
+/// with the same weights as the Whitted integrator. Returns the next ray with the factor to apply to
+/// the light it brings back, or None when the path is absorbed.
+fn scatter(infos: &IntersectionInfos, material: &Material, color: Color, rng: &mut Rng) -> Option<(Ray, Color)> {
+    let (reflected, refracted) = if material.reflective > 0.0 && material.transparency > 0.0 {
+        let reflectance = infos.schlick();
+        (material.reflective * reflectance, material.transparency * (1.0 - reflectance))
+    } else {
+        (material.reflective, material.transparency)
+    };
+
+    // Pick one of the events proportionally to its weight, the diffuse one always weighting 1
+    let total = 1.0 + reflected + refracted;
+    let choice = rng.next_f64() * total;
+
+    if choice < reflected {
+        Some((Ray::new(infos.over_point, infos.reflectv), Color::WHITE * total))
+    } else if choice < reflected + refracted {
+        // Total internal reflection is absorbed, like in the Whitted integrator
+        infos.refractv().map(|direction| (Ray::new(infos.under_point, direction), Color::WHITE * total))
+    } else {
+        match &material.pbr {
+            Some(pbr) => pbr
+                .sample(color, infos.normal, infos.eyev, rng)
+                .map(|sample| (Ray::new(infos.over_point, sample.direction), sample.weight * total)),
+            None => {
+                // Cosine sampling cancels out the lambertian cosine and 1 / pi terms
+                let direction = cosine_sample_hemisphere(infos.normal, rng);
+                Some((Ray::new(infos.over_point, direction), color * material.diffuse * total))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use crate::common::{Object, PointLight, Transform};
+
+    use super::*;
+
+    const PATHS: usize = 20_000;
+
+    fn average(ray: &Ray, scene: &Scene, max_depth: u32) -> Color {
+        let mut rng = Rng::new(3);
+        let mut sum = Color::BLACK;
+        for _ in 0..PATHS {
+            sum += path_trace(ray, scene, max_depth, &mut rng);
+        }
+        sum / PATHS as f64
+    }
+
+    fn glowing() -> Material {
+        Material::new()
+            .with_color(Color::BLACK)
+            .with_diffuse(0.0)
+            .with_specular(0.0)
+            .with_emission(Color::WHITE, 1.0)
+    }
+
+    #[test]
+    fn a_path_seeing_an_emissive_object_gets_its_emission() {
+        let mut scene = Scene::default();
+        scene.add_object(
+            Object::new_sphere()
+            .with_material(Material::new().with_emission(Color::new(1.0, 0.5, 0.25), 2.0))
+        );
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let c = path_trace(&r, &scene, 0, &mut Rng::default());
+        assert_eq!(c, Color::new(2.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn a_diffuse_sphere_inside_a_glowing_sphere() {
+        // Every path bounces once on the inner sphere then reaches the glowing one
+        let mut scene = Scene::default();
+        scene.add_object(
+            Object::new_sphere()
+            .with_transform(Transform::from_scale(dvec3(10.0, 10.0, 10.0)))
+            .with_material(glowing())
+        );
+        scene.add_object(
+            Object::new_sphere()
+            .with_material(Material::new().with_diffuse(0.5))
+        );
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let c = average(&r, &scene, 5);
+        assert_eq!(c, Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn an_emissive_sphere_lights_the_floor_below_it() {
+        // A sphere of radiance L and radius r seen at distance d gives off an irradiance of
+        // pi * L * (r / d)^2, a white lambertian floor then reflects L * (r / d)^2
+        let mut scene = Scene::default();
+        scene.add_object(
+            Object::new_plane()
+            .with_material(Material::new().with_diffuse(1.0))
+        );
+        scene.add_object(
+            Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(0.0, 2.0, 0.0)))
+            .with_material(glowing())
+        );
+        let r = Ray::new(dvec3(0.0, 1.0, -1.0), dvec3(0.0, -1.0, 1.0).normalize());
+        let c = average(&r, &scene, 1);
+        assert!((c.r - 0.25).abs() < 0.02, "floor reflects {}", c.r);
+    }
+
+    #[test]
+    fn the_floor_is_dark_without_emission() {
+        let mut scene = Scene::default();
+        scene.add_object(
+            Object::new_plane()
+            .with_material(Material::new().with_diffuse(1.0))
+        );
+        scene.add_object(
+            Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(0.0, 2.0, 0.0)))
+            .with_material(glowing().with_emission(Color::BLACK, 1.0))
+        );
+        let r = Ray::new(dvec3(0.0, 1.0, -1.0), dvec3(0.0, -1.0, 1.0).normalize());
+        assert_eq!(average(&r, &scene, 1), Color::BLACK);
+    }
+
+    #[test]
+    fn lights_are_sampled_at_every_bounce() {
+        let mut scene = Scene::default();
+        scene.add_light(PointLight::new(dvec3(0.0, 0.0, -10.0), Color::WHITE));
+        scene.add_object(
+            Object::new_sphere()
+            .with_material(Material::new().with_diffuse(1.0).with_specular(0.0))
+        );
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let c = path_trace(&r, &scene, 0, &mut Rng::default());
+        assert_eq!(c, Color::new(1.0, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,290 @@
+use glam::{DVec2, DVec3};
+
+use crate::{common::{color::Color, shapes::{Cube, Cylinder, Plane, Shape, Sphere}, Object, Pattern, PatternKind, UvMapping}, raytracer::cpu::intersections::{Hittable, HittableShape}};
+
+impl Pattern {
+    /// Compute the color of the pattern at a world space point on an object
+    pub fn color_at_object(&self, object: &Object, world_point: DVec3) -> Color {
+        let object_point = object.world_to_object(world_point);
+        self.color_at_on(object.shape(), object_point)
+    }
+
+    /// Compute the color of the pattern at a point expressed in the parent space of the pattern,
+    /// which is the object space for a top level pattern
+    pub fn color_at(&self, point: DVec3) -> Color {
+        self.color_at_on(&Shape::Plane(Plane::new()), point)
+    }
+
+    /// Same as `color_at`, on a shape providing the texture coordinates of `UvMapping::Object`
+    pub fn color_at_on(&self, shape: &Shape, point: DVec3) -> Color {
+        let point = self.transform().inverse_matrix().transform_point3(point);
+        match self.kind() {
+            PatternKind::Solid(color) => *color,
+            PatternKind::Stripe(a, b) => {
+                if point.x.floor().rem_euclid(2.0) == 0.0 {
+                    a.color_at_on(shape, point)
+                } else {
+                    b.color_at_on(shape, point)
+                }
+            },
+            PatternKind::Gradient(a, b) => {
+                let a = a.color_at_on(shape, point);
+                let b = b.color_at_on(shape, point);
+                a + (b - a) * (point.x - point.x.floor())
+            },
+            PatternKind::Ring(a, b) => {
+                let distance = (point.x * point.x + point.z * point.z).sqrt();
+                if distance.floor().rem_euclid(2.0) == 0.0 {
+                    a.color_at_on(shape, point)
+                } else {
+                    b.color_at_on(shape, point)
+                }
+            },
+            PatternKind::Checker(a, b) => {
+                let sum = point.x.floor() + point.y.floor() + point.z.floor();
+                if sum.rem_euclid(2.0) == 0.0 {
+                    a.color_at_on(shape, point)
+                } else {
+                    b.color_at_on(shape, point)
+                }
+            },
+            PatternKind::Blend(a, b, factor) => {
+                a.color_at_on(shape, point) * (1.0 - factor) + b.color_at_on(shape, point) * *factor
+            },
+            PatternKind::Texture(texture, mapping) => texture.sample(mapping.uv_at(shape, point)),
+            PatternKind::Noise(a, b, noise) => {
+                let a = a.color_at_on(shape, point);
+                let b = b.color_at_on(shape, point);
+                a + (b - a) * noise.value(point)
+            },
+            PatternKind::Perturb(pattern, noise, scale) => {
+                pattern.color_at_on(shape, point + noise.displacement(point) * *scale)
+            },
+        }
+    }
+}
+
+impl UvMapping {
+    /// Compute the texture coordinates of a point using the mapping of the matching shape,
+    /// or the own mapping of the shape the point is on
+    pub fn uv_at(&self, shape: &Shape, point: DVec3) -> DVec2 {
+        match self {
+            UvMapping::Spherical => Sphere::new().uv_at(point),
+            UvMapping::Planar => Plane::new().uv_at(point),
+            UvMapping::Cylindrical => Cylinder::new().uv_at(point),
+            UvMapping::Cube => Cube::new().uv_at(point),
+            UvMapping::Object => shape.uv_at(point),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use std::sync::Arc;
+
+    use crate::{common::{shapes::Disk, ImageTexture, Material, Noise, PointLight, Rng, Scene, TextureFilter, Transform}, raytracer::cpu::{CpuRaytracer, Ray, DEFAULT_MAX_DEPTH}};
+
+    use super::*;
+
+    fn stripe_pattern() -> Pattern {
+        Pattern::new_stripe(Color::WHITE, Color::BLACK)
+    }
+
+    #[test]
+    fn a_stripe_pattern_is_constant_in_y() {
+        let p = stripe_pattern();
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.0, 1.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.0, 2.0, 0.0)), Color::WHITE);
+    }
+
+    #[test]
+    fn a_stripe_pattern_is_constant_in_z() {
+        let p = stripe_pattern();
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 1.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 2.0)), Color::WHITE);
+    }
+
+    #[test]
+    fn a_stripe_pattern_alternates_in_x() {
+        let p = stripe_pattern();
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.9, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(1.0, 0.0, 0.0)), Color::BLACK);
+        assert_eq!(p.color_at(dvec3(-0.1, 0.0, 0.0)), Color::BLACK);
+        assert_eq!(p.color_at(dvec3(-1.0, 0.0, 0.0)), Color::BLACK);
+        assert_eq!(p.color_at(dvec3(-1.1, 0.0, 0.0)), Color::WHITE);
+    }
+
+    #[test]
+    fn stripes_with_an_object_transformation() {
+        let o = Object::new_sphere()
+            .with_transform(Transform::from_scale(dvec3(2.0, 2.0, 2.0)));
+        let p = stripe_pattern();
+        assert_eq!(p.color_at_object(&o, dvec3(1.5, 0.0, 0.0)), Color::WHITE);
+    }
+
+    #[test]
+    fn stripes_with_a_pattern_transformation() {
+        let o = Object::new_sphere();
+        let p = stripe_pattern()
+            .with_transform(Transform::from_scale(dvec3(2.0, 2.0, 2.0)));
+        assert_eq!(p.color_at_object(&o, dvec3(1.5, 0.0, 0.0)), Color::WHITE);
+    }
+
+    #[test]
+    fn stripes_with_both_an_object_and_a_pattern_transformation() {
+        let o = Object::new_sphere()
+            .with_transform(Transform::from_scale(dvec3(2.0, 2.0, 2.0)));
+        let p = stripe_pattern()
+            .with_transform(Transform::from_translation(dvec3(0.5, 0.0, 0.0)));
+        assert_eq!(p.color_at_object(&o, dvec3(2.5, 0.0, 0.0)), Color::WHITE);
+    }
+
+    #[test]
+    fn a_gradient_linearly_interpolates_between_colors() {
+        let p = Pattern::new_gradient(Color::WHITE, Color::BLACK);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.25, 0.0, 0.0)), Color::new(0.75, 0.75, 0.75));
+        assert_eq!(p.color_at(dvec3(0.5, 0.0, 0.0)), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(p.color_at(dvec3(0.75, 0.0, 0.0)), Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn a_ring_should_extend_in_both_x_and_z() {
+        let p = Pattern::new_ring(Color::WHITE, Color::BLACK);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(1.0, 0.0, 0.0)), Color::BLACK);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 1.0)), Color::BLACK);
+        // 0.708 = just slightly more than sqrt(2)/2
+        assert_eq!(p.color_at(dvec3(0.708, 0.0, 0.708)), Color::BLACK);
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_x() {
+        let p = Pattern::new_checker(Color::WHITE, Color::BLACK);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.99, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(1.01, 0.0, 0.0)), Color::BLACK);
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_y() {
+        let p = Pattern::new_checker(Color::WHITE, Color::BLACK);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.0, 0.99, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.0, 1.01, 0.0)), Color::BLACK);
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_z() {
+        let p = Pattern::new_checker(Color::WHITE, Color::BLACK);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.0)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.99)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 1.01)), Color::BLACK);
+    }
+
+    fn render_from_above(object: Object, points: &[(f64, f64)]) -> Vec<Color> {
+        let mut scene = Scene::default();
+        scene.add_light(PointLight::new(dvec3(0.0, 10.0, 0.0), Color::WHITE));
+        scene.add_object(object);
+        points
+            .iter()
+            .map(|&(x, z)| {
+                let r = Ray::new(dvec3(x, 5.0, z), dvec3(0.0, -1.0, 0.0));
+                CpuRaytracer::color_at(&r, &scene, DEFAULT_MAX_DEPTH, &mut Rng::default())
+            })
+            .collect()
+    }
+
+    fn unlit(pattern: Pattern) -> Material {
+        Material::default()
+            .with_pattern(pattern)
+            .with_ambient(1.0)
+            .with_diffuse(0.0)
+            .with_specular(0.0)
+    }
+
+    #[test]
+    fn an_image_is_rendered_with_the_coordinates_of_a_quad() {
+        let texture = ImageTexture::new(2, 2, vec![Color::RED, Color::GREEN, Color::BLUE, Color::WHITE])
+            .with_filter(TextureFilter::Nearest);
+        let pattern = Pattern::new_texture(Arc::new(texture), UvMapping::Object);
+        // Slanted so that the texture follows the edges and not the world axes
+        let quad = Object::new_quad(dvec3(-2.0, 0.0, -1.0), dvec3(2.0, 0.0, 0.0), dvec3(2.0, 0.0, 2.0))
+            .with_material(unlit(pattern));
+
+        let colors = render_from_above(quad, &[(-1.25, -0.5), (0.25, -0.5), (-0.25, 0.5), (1.25, 0.5), (-1.5, 0.5)]);
+        assert_eq!(colors, vec![Color::BLUE, Color::WHITE, Color::RED, Color::GREEN, Color::BLACK]);
+    }
+
+    #[test]
+    fn an_image_is_rendered_with_the_coordinates_of_an_annulus() {
+        // Blue on the inner half of the ring, red on the outer half
+        let texture = ImageTexture::new(1, 2, vec![Color::RED, Color::BLUE])
+            .with_filter(TextureFilter::Nearest);
+        let pattern = Pattern::new_texture(Arc::new(texture), UvMapping::Object);
+        let annulus = Object::new(Shape::Disk(Disk::new(1.0).with_inner_radius(0.5)))
+            .with_material(unlit(pattern));
+
+        let colors = render_from_above(annulus, &[(0.6, 0.0), (0.0, -0.65), (0.9, 0.0), (-0.6, 0.6), (0.3, 0.0)]);
+        assert_eq!(colors, vec![Color::BLUE, Color::BLUE, Color::RED, Color::RED, Color::BLACK]);
+    }
+
+    #[test]
+    fn a_texture_pattern_uses_its_uv_mapping() {
+        let texture = ImageTexture::new(2, 1, vec![Color::RED, Color::BLUE])
+            .with_filter(TextureFilter::Nearest);
+        let p = Pattern::new_texture(Arc::new(texture), UvMapping::Planar);
+        assert_eq!(p.color_at(dvec3(0.25, 0.0, 0.5)), Color::RED);
+        assert_eq!(p.color_at(dvec3(0.75, 0.0, 0.5)), Color::BLUE);
+        assert_eq!(p.color_at(dvec3(1.25, 0.0, 0.5)), Color::RED);
+    }
+
+    #[test]
+    fn blending_two_patterns() {
+        let p = Pattern::new_blend(Color::WHITE, Color::RED, 0.5);
+        assert_eq!(p.color_at(dvec3(0.0, 0.0, 0.0)), Color::new(1.0, 0.5, 0.5));
+    }
+
+    #[test]
+    fn nesting_patterns_inside_checker_cells() {
+        let stripes = Pattern::new_stripe(Color::RED, Color::BLUE)
+            .with_transform(Transform::from_scale(dvec3(0.5, 0.5, 0.5)));
+        let p = Pattern::new_checker(stripes, Color::BLACK);
+        assert_eq!(p.color_at(dvec3(0.25, 0.0, 0.0)), Color::RED);
+        assert_eq!(p.color_at(dvec3(0.75, 0.0, 0.0)), Color::BLUE);
+        assert_eq!(p.color_at(dvec3(1.25, 0.0, 0.0)), Color::BLACK);
+    }
+
+    #[test]
+    fn a_noise_pattern_mixes_two_patterns() {
+        let p = Pattern::new_noise(Color::BLACK, Color::WHITE, Noise::Perlin);
+        // Perlin noise is 0 on integer points, halfway once remapped
+        assert_eq!(p.color_at(dvec3(1.0, 2.0, 3.0)), Color::new(0.5, 0.5, 0.5));
+        let c = p.color_at(dvec3(1.3, 2.6, 3.1));
+        assert!(c.r >= 0.0 && c.r <= 1.0);
+        assert_eq!(c.r, c.g);
+        assert_eq!(c.g, c.b);
+    }
+
+    #[test]
+    fn a_perturbation_without_scale_keeps_the_pattern() {
+        let p = Pattern::new_perturb(stripe_pattern(), Noise::fbm(4), 0.0);
+        assert_eq!(p.color_at(dvec3(0.9, 0.3, 0.2)), Color::WHITE);
+        assert_eq!(p.color_at(dvec3(1.1, 0.3, 0.2)), Color::BLACK);
+    }
+
+    #[test]
+    fn a_perturbation_moves_the_input_point() {
+        let p = Pattern::new_perturb(Pattern::new_gradient(Color::BLACK, Color::WHITE), Noise::Perlin, 0.2);
+        let point = dvec3(0.4, 0.3, 0.2);
+        let displaced = point + Noise::Perlin.displacement(point) * 0.2;
+        let expected = displaced.x - displaced.x.floor();
+        assert_eq!(p.color_at(point), Color::new(expected, expected, expected));
+        assert_ne!(displaced, point);
+    }
+}
@@ -0,0 +1,156 @@
+/// Number of bisection steps used to isolate a root, enough to reach the precision of a f64
+const BISECTION_STEPS: usize = 100;
+
+/// Evaluate a polynomial given its coefficients from the highest degree to the constant term
+pub fn evaluate(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().fold(0.0, |value, coefficient| value * x + coefficient)
+}
+
+/// Real roots of `a x^4 + b x^3 + c x^2 + d x + e`, sorted, double roots appearing twice
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    real_roots(&[a, b, c, d, e])
+}
+
+/// Real roots of a polynomial given its coefficients from the highest degree to the constant term.
+/// The polynomial is monotonic between the roots of its derivative, so every such interval holds at most one root
+/// which is isolated by bisection. This avoids the cancellations of the closed form formulas.
+/// A root of the derivative on which the polynomial vanishes is a double root, reported twice.
+pub fn real_roots(coefficients: &[f64]) -> Vec<f64> {
+    // Drop the leading null coefficients to get the actual degree
+    let first = coefficients.iter().position(|c| *c != 0.0).unwrap_or(coefficients.len());
+    let coefficients = &coefficients[first..];
+
+    match coefficients.len() {
+        0 | 1 => return Vec::new(),
+        2 => return vec![-coefficients[1] / coefficients[0]],
+        _ => {},
+    }
+
+    let degree = coefficients.len() - 1;
+    let derivative: Vec<f64> = coefficients[..degree]
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c * (degree - i) as f64)
+        .collect();
+    let mut critical_points = real_roots(&derivative);
+    critical_points.dedup();
+
+    // Every root is within the Cauchy bound
+    let bound = 1.0 + coefficients[1..]
+        .iter()
+        .map(|c| (c / coefficients[0]).abs())
+        .fold(0.0, f64::max);
+    // Values under this threshold are rounding noise around a root
+    let tolerance = 1e-12 * coefficients.iter().map(|c| c.abs()).fold(0.0, f64::max);
+
+    let mut points = vec![-bound];
+    points.extend(critical_points.iter().copied().filter(|x| x.abs() < bound));
+    points.push(bound);
+
+    let values: Vec<f64> = points
+        .iter()
+        .map(|x| {
+            let value = evaluate(coefficients, *x);
+            if value.abs() <= tolerance { 0.0 } else { value }
+        })
+        .collect();
+
+    let mut roots = Vec::new();
+    for i in 0..points.len() - 1 {
+        let (low, high) = (points[i], points[i + 1]);
+        let (low_value, high_value) = (values[i], values[i + 1]);
+
+        if i > 0 && low_value == 0.0 {
+            // Tangent to the axis on a critical point
+            roots.push(low);
+            roots.push(low);
+        }
+        if low_value * high_value < 0.0 {
+            roots.push(bisect(coefficients, low, high, low_value));
+        }
+    }
+    roots
+}
+
+/// Find the root of a polynomial changing sign once between two bounds
+fn bisect(coefficients: &[f64], mut low: f64, mut high: f64, low_value: f64) -> f64 {
+    let low_sign = low_value.signum();
+    for _ in 0..BISECTION_STEPS {
+        let middle = 0.5 * (low + high);
+        if middle <= low || middle >= high {
+            break;
+        }
+        let value = evaluate(coefficients, middle);
+        if value == 0.0 {
+            return middle;
+        }
+        if value.signum() == low_sign {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    0.5 * (low + high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    fn assert_roots(roots: &[f64], expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{:?} != {:?}", roots, expected);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < EPSILON, "{:?} != {:?}", roots, expected);
+        }
+    }
+
+    #[test]
+    fn evaluating_a_polynomial() {
+        assert_eq!(evaluate(&[2.0, -3.0, 0.0, 5.0], 2.0), 9.0);
+    }
+
+    #[test]
+    fn a_quartic_with_four_distinct_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(&solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0), &[1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn a_quartic_without_real_roots() {
+        assert_roots(&solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0), &[]);
+        assert_roots(&solve_quartic(1.0, 0.0, 3.0, 0.0, 2.0), &[]);
+    }
+
+    #[test]
+    fn double_roots_are_reported_twice() {
+        // (x - 1)^2 (x + 2)^2
+        assert_roots(&solve_quartic(1.0, 2.0, -3.0, -4.0, 4.0), &[-2.0, -2.0, 1.0, 1.0]);
+        // (x - 1)^2 (x^2 + 1)
+        assert_roots(&solve_quartic(1.0, -2.0, 2.0, -2.0, 1.0), &[1.0, 1.0]);
+    }
+
+    #[test]
+    fn roots_far_from_the_origin_are_accurate() {
+        // (x - 1000)(x - 1000.5)(x + 3)(x + 3.25)
+        let coefficients = [1000.0, 1000.5, -3.0, -3.25]
+            .iter()
+            .fold(vec![1.0], |poly, root| {
+                let mut next = poly.clone();
+                next.push(0.0);
+                for i in 0..poly.len() {
+                    next[i + 1] -= root * poly[i];
+                }
+                next
+            });
+        assert_roots(&real_roots(&coefficients), &[-3.25, -3.0, 1000.0, 1000.5]);
+    }
+
+    #[test]
+    fn lower_degrees_are_handled() {
+        assert_roots(&solve_quartic(0.0, 0.0, 1.0, 0.0, -4.0), &[-2.0, 2.0]);
+        assert_roots(&solve_quartic(0.0, 0.0, 0.0, 2.0, -1.0), &[0.5]);
+        assert_roots(&solve_quartic(0.0, 0.0, 0.0, 0.0, 3.0), &[]);
+    }
+}
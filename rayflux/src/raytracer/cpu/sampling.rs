@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+
+use glam::DVec3;
+
+use crate::common::Rng;
+
+/// Build two tangent vectors forming an orthonormal basis with a normalized vector
+pub fn orthonormal_basis(n: DVec3) -> (DVec3, DVec3) {
+    // Building an Orthonormal Basis, Revisited (Duff et al.)
+    let sign = 1.0_f64.copysign(n.z);
+    let a = -1.0 / (sign + n.z);
+    let b = n.x * n.y * a;
+    let t = DVec3::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x);
+    let bt = DVec3::new(b, sign + n.y * n.y * a, -n.y);
+    (t, bt)
+}
+
+/// Express a direction given in the local frame (z being the normal) in world space
+pub fn local_to_world(local: DVec3, normal: DVec3) -> DVec3 {
+    let (t, b) = orthonormal_basis(normal);
+    t * local.x + b * local.y + normal * local.z
+}
+
+/// Sample a direction around a normal, with a density proportional to the cosine. Pdf is cos / pi.
+pub fn cosine_sample_hemisphere(normal: DVec3, rng: &mut Rng) -> DVec3 {
+    let r = rng.next_f64().sqrt();
+    let phi = 2.0 * PI * rng.next_f64();
+    let local = DVec3::new(r * phi.cos(), r * phi.sin(), (1.0 - r * r).max(0.0).sqrt());
+    local_to_world(local, normal)
+}
+
+/// Sample a direction around a normal, uniformly. Pdf is 1 / (2 pi).
+pub fn uniform_sample_hemisphere(normal: DVec3, rng: &mut Rng) -> DVec3 {
+    let z = rng.next_f64();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.next_f64();
+    let local = DVec3::new(r * phi.cos(), r * phi.sin(), z);
+    local_to_world(local, normal)
+}
+
+/// Sample a direction uniformly over the whole sphere. Pdf is 1 / (4 pi).
+pub fn uniform_sample_sphere(rng: &mut Rng) -> DVec3 {
+    let z = 1.0 - 2.0 * rng.next_f64();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.next_f64();
+    DVec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    #[test]
+    fn the_basis_is_orthonormal() {
+        let normals = [
+            dvec3(0.0, 0.0, 1.0),
+            dvec3(0.0, 0.0, -1.0),
+            dvec3(1.0, 2.0, 3.0).normalize(),
+            dvec3(-0.3, 0.9, -0.1).normalize(),
+        ];
+        for n in normals {
+            let (t, b) = orthonormal_basis(n);
+            assert!((t.length() - 1.0).abs() < EPSILON);
+            assert!((b.length() - 1.0).abs() < EPSILON);
+            assert!(t.dot(n).abs() < EPSILON);
+            assert!(b.dot(n).abs() < EPSILON);
+            assert!(t.dot(b).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn hemisphere_samples_are_above_the_surface() {
+        let mut rng = Rng::new(1);
+        let n = dvec3(1.0, 1.0, 0.0).normalize();
+        for _ in 0..1000 {
+            let d = cosine_sample_hemisphere(n, &mut rng);
+            assert!(d.dot(n) >= 0.0);
+            assert!((d.length() - 1.0).abs() < EPSILON);
+            let d = uniform_sample_hemisphere(n, &mut rng);
+            assert!(d.dot(n) >= 0.0);
+            assert!((d.length() - 1.0).abs() < EPSILON);
+        }
+    }
+}
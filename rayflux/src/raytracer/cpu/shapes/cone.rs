@@ -0,0 +1,224 @@
+use std::f64::consts::PI;
+
+use glam::{DVec2, DVec3};
+
+use crate::{common::{shapes::Cone, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray, EPSILON}};
+
+impl HittableShape for Cone {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        // The radius of a cap is the absolute value of its y
+        fn check_cap(ray: &Ray, t: f64, y: f64) -> bool {
+            let x = ray.origin.x + t * ray.direction.x;
+            let z = ray.origin.z + t * ray.direction.z;
+
+            (x * x + z * z) <= y * y
+        }
+
+        fn intersect_caps<'a>(cone: &Cone, ray: &Ray, object: &'a Object) -> Vec<Intersection<'a>> {
+            let mut xs = Vec::default();
+            if !cone.closed || f64::abs(ray.direction.y) < f64::EPSILON {
+                return xs;
+            }
+
+            let t = (cone.min - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, cone.min) {
+                xs.push(Intersection::new(t, object));
+            }
+
+            let t = (cone.max - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, cone.max) {
+                xs.push(Intersection::new(t, object));
+            }
+
+            xs
+        }
+
+        let in_bounds = |t: f64| {
+            let y = ray.origin.y + t * ray.direction.y;
+            self.min < y && y < self.max
+        };
+
+        let a = ray.direction.x * ray.direction.x
+            - ray.direction.y * ray.direction.y
+            + ray.direction.z * ray.direction.z;
+        let b = 2.0 * ray.origin.x * ray.direction.x
+            - 2.0 * ray.origin.y * ray.direction.y
+            + 2.0 * ray.origin.z * ray.direction.z;
+        let c = ray.origin.x * ray.origin.x
+            - ray.origin.y * ray.origin.y
+            + ray.origin.z * ray.origin.z;
+
+        let mut xs = Vec::default();
+
+        if f64::abs(a) < EPSILON {
+            // The ray is parallel to one of the halves, so it can only cross the other one
+            if f64::abs(b) >= EPSILON {
+                let t = -c / (2.0 * b);
+                if in_bounds(t) {
+                    xs.push(Intersection::new(t, object));
+                }
+            }
+        } else {
+            let disc = b*b - 4.0*a*c;
+            if disc < 0.0 {
+                return Intersections::new();
+            }
+
+            let mut t0 = (-b - f64::sqrt(disc)) / (2.0 * a);
+            let mut t1 = (-b + f64::sqrt(disc)) / (2.0 * a);
+
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            if in_bounds(t0) {
+                xs.push(Intersection::new(t0, object));
+            }
+            if in_bounds(t1) {
+                xs.push(Intersection::new(t1, object));
+            }
+        }
+
+        xs.append(&mut intersect_caps(self, ray, object));
+        Intersections::new()
+        .with_intersections(xs)
+    }
+
+    fn normal_at(&self, point: DVec3) -> DVec3 {
+        let dist = point.x * point.x + point.z * point.z;
+        if dist < self.max * self.max && point.y >= self.max - f64::EPSILON {
+            DVec3::new(0.0, 1.0, 0.0)
+        } else if dist < self.min * self.min && point.y <= self.min + f64::EPSILON {
+            DVec3::new(0.0, -1.0, 0.0)
+        } else {
+            let mut y = dist.sqrt();
+            if point.y > 0.0 {
+                y = -y;
+            }
+            DVec3::new(point.x, y, point.z)
+        }
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        let dist = point.x * point.x + point.z * point.z;
+        let radius = point.y.abs();
+        if radius > 0.0 && dist < radius * radius * (1.0 - 1.0e-6) {
+            // Points strictly inside the cone lie on a cap, mapped as a disk
+            DVec2::new((point.x / radius + 1.0) / 2.0, (1.0 - point.z / radius) / 2.0)
+        } else {
+            let theta = point.x.atan2(point.z);
+            let raw_u = theta / (2.0 * PI);
+            let u = 1.0 - (raw_u + 0.5);
+            // Not wrapped, the texture repeats every unit along the axis
+            DVec2::new(u, point.y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{dvec2, dvec3};
+
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray() {
+        let shape = Object::new_cone();
+        let cone = Cone::new();
+        let examples = [
+            (dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0), 5.0, 5.0),
+            (dvec3(0.0, 0.0, -5.0), dvec3(1.0, 1.0, 1.0), 8.66025, 8.66025),
+            (dvec3(1.0, 1.0, -5.0), dvec3(-0.5, -1.0, 1.0), 4.55006, 49.44994),
+        ];
+        for (origin, direction, t0, t1) in examples {
+            let r = Ray::new(origin, direction.normalize());
+            let xs = cone.intersect(&r, &shape);
+            assert_eq!(xs.count(), 2);
+            assert!((xs[0].t() - t0).abs() < EPSILON);
+            assert!((xs[1].t() - t1).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray_parallel_to_one_of_its_halves() {
+        let shape = Object::new_cone();
+        let cone = Cone::new();
+        let r = Ray::new(dvec3(0.0, 0.0, -1.0), dvec3(0.0, 1.0, 1.0).normalize());
+        let xs = cone.intersect(&r, &shape);
+        assert_eq!(xs.count(), 1);
+        assert!((xs[0].t() - 0.35355).abs() < EPSILON);
+    }
+
+    #[test]
+    fn a_ray_along_the_surface_of_a_cone_misses() {
+        let shape = Object::new_cone();
+        let cone = Cone::new();
+        let r = Ray::new(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 1.0, 1.0).normalize());
+        let xs = cone.intersect(&r, &shape);
+        assert_eq!(xs.count(), 0);
+    }
+
+    #[test]
+    fn intersecting_a_cone_end_caps() {
+        let shape = Object::new_cone();
+        let cone = Cone { min: -0.5, max: 0.5, closed: true };
+        let examples = [
+            (dvec3(0.0, 0.0, -5.0), dvec3(0.0, 1.0, 0.0), 0),
+            (dvec3(0.0, 0.0, -0.25), dvec3(0.0, 1.0, 1.0), 2),
+            (dvec3(0.0, 0.0, -0.25), dvec3(0.0, 1.0, 0.0), 4),
+        ];
+        for (origin, direction, count) in examples {
+            let r = Ray::new(origin, direction.normalize());
+            let xs = cone.intersect(&r, &shape);
+            assert_eq!(xs.count(), count);
+        }
+    }
+
+    #[test]
+    fn a_truncated_cone_is_open_by_default() {
+        let shape = Object::new_cone();
+        let cone = Cone { min: -0.5, max: 0.5, closed: false };
+        let r = Ray::new(dvec3(0.0, 0.0, -0.25), dvec3(0.0, 1.0, 0.0));
+        let xs = cone.intersect(&r, &shape);
+        assert_eq!(xs.count(), 2);
+    }
+
+    #[test]
+    fn computing_the_normal_vector_on_a_cone() {
+        let cone = Cone::new();
+        let examples = [
+            (dvec3(0.0, 0.0, 0.0), dvec3(0.0, 0.0, 0.0)),
+            (dvec3(1.0, 1.0, 1.0), dvec3(1.0, -2.0_f64.sqrt(), 1.0)),
+            (dvec3(-1.0, -1.0, 0.0), dvec3(-1.0, 1.0, 0.0)),
+        ];
+        for (point, normal) in examples {
+            assert!(cone.normal_at(point).abs_diff_eq(normal, EPSILON));
+        }
+    }
+
+    #[test]
+    fn the_normal_vector_on_a_cone_end_caps() {
+        let cone = Cone { min: -1.0, max: 2.0, closed: true };
+        let examples = [
+            (dvec3(0.0, -1.0, 0.0), dvec3(0.0, -1.0, 0.0)),
+            (dvec3(0.5, -1.0, 0.0), dvec3(0.0, -1.0, 0.0)),
+            (dvec3(0.0, 2.0, 0.0), dvec3(0.0, 1.0, 0.0)),
+            (dvec3(1.5, 2.0, 0.0), dvec3(0.0, 1.0, 0.0)),
+        ];
+        for (point, normal) in examples {
+            assert_eq!(cone.normal_at(point), normal);
+        }
+    }
+
+    #[test]
+    fn using_a_conical_mapping_on_a_3d_point() {
+        let cone = Cone { min: -1.0, max: 2.0, closed: true };
+        assert!(cone.uv_at(dvec3(0.0, 1.0, -1.0)).abs_diff_eq(dvec2(0.0, 1.0), EPSILON));
+        assert!(cone.uv_at(dvec3(2.0, 2.0, 0.0)).abs_diff_eq(dvec2(0.25, 2.0), EPSILON));
+        // Caps are mapped as disks
+        assert!(cone.uv_at(dvec3(0.0, 2.0, 0.0)).abs_diff_eq(dvec2(0.5, 0.5), EPSILON));
+        assert!(cone.uv_at(dvec3(1.0, 2.0, -1.0)).abs_diff_eq(dvec2(0.75, 0.75), EPSILON));
+    }
+}
@@ -0,0 +1,180 @@
+use glam::{DVec2, DVec3};
+
+use crate::{common::{shapes::{Csg, CsgOperation}, Object}, raytracer::cpu::{intersections::{Hittable, HittableShape, Intersections}, Ray}};
+
+impl CsgOperation {
+    /// Check if an intersection is on the surface of the combined shape.
+    /// `left_hit` tells which operand is hit, `inside_left` and `inside_right` whether the hit is inside each operand.
+    pub fn intersection_allowed(&self, left_hit: bool, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => (left_hit && !inside_right) || (!left_hit && !inside_left),
+            CsgOperation::Intersection => (left_hit && inside_right) || (!left_hit && inside_left),
+            CsgOperation::Difference => (left_hit && !inside_right) || (!left_hit && inside_left),
+        }
+    }
+}
+
+impl Csg {
+    /// Keep the sorted intersections lying on the surface of the combined shape
+    pub fn filter_intersections<'a>(&self, intersections: Intersections<'a>) -> Intersections<'a> {
+        // Every crossing of an operand's surface toggles being inside it
+        let mut inside_left = false;
+        let mut inside_right = false;
+
+        let mut result = Intersections::new();
+        for intersection in intersections.move_all() {
+            let left_hit = self.left().includes(intersection.object());
+
+            if self.operation().intersection_allowed(left_hit, inside_left, inside_right) {
+                result.push(intersection);
+            }
+
+            if left_hit {
+                inside_left = !inside_left;
+            } else {
+                inside_right = !inside_right;
+            }
+        }
+        result
+    }
+}
+
+impl HittableShape for Csg {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        // The operands are borrowed from the object to report them as the intersected objects
+        let [left, right] = object.children() else {
+            unreachable!("A csg always has two operands");
+        };
+        let mut intersections = left.intersect(ray);
+        intersections.append(right.intersect(ray));
+        self.filter_intersections(intersections.sort())
+    }
+
+    fn normal_at(&self, _point: DVec3) -> DVec3 {
+        unreachable!("Intersections are reported on the operands of a csg, never on the csg itself")
+    }
+
+    fn uv_at(&self, _point: DVec3) -> DVec2 {
+        unreachable!("Intersections are reported on the operands of a csg, never on the csg itself")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+    use crate::{common::{shapes::{Cylinder, Shape}, Transform}, raytracer::cpu::intersections::Intersection};
+    use super::*;
+
+    fn csg(object: &Object) -> &Csg {
+        match object.shape() {
+            Shape::Csg(csg) => csg,
+            _ => panic!("expected a csg"),
+        }
+    }
+
+    #[test]
+    fn csg_is_created_with_an_operation_and_two_shapes() {
+        let c = Object::new_csg(CsgOperation::Union, Object::new_sphere(), Object::new_cube());
+        assert_eq!(csg(&c).operation(), CsgOperation::Union);
+        assert_eq!(csg(&c).left().shape(), Object::new_sphere().shape());
+        assert_eq!(csg(&c).right().shape(), Object::new_cube().shape());
+    }
+
+    #[test]
+    fn evaluating_the_rule_for_a_csg_operation() {
+        let rules = [
+            (CsgOperation::Union, true, true, true, false),
+            (CsgOperation::Union, true, true, false, true),
+            (CsgOperation::Union, true, false, true, false),
+            (CsgOperation::Union, true, false, false, true),
+            (CsgOperation::Union, false, true, true, false),
+            (CsgOperation::Union, false, true, false, false),
+            (CsgOperation::Union, false, false, true, true),
+            (CsgOperation::Union, false, false, false, true),
+            (CsgOperation::Intersection, true, true, true, true),
+            (CsgOperation::Intersection, true, true, false, false),
+            (CsgOperation::Intersection, true, false, true, true),
+            (CsgOperation::Intersection, true, false, false, false),
+            (CsgOperation::Intersection, false, true, true, true),
+            (CsgOperation::Intersection, false, true, false, true),
+            (CsgOperation::Intersection, false, false, true, false),
+            (CsgOperation::Intersection, false, false, false, false),
+            (CsgOperation::Difference, true, true, true, false),
+            (CsgOperation::Difference, true, true, false, true),
+            (CsgOperation::Difference, true, false, true, false),
+            (CsgOperation::Difference, true, false, false, true),
+            (CsgOperation::Difference, false, true, true, true),
+            (CsgOperation::Difference, false, true, false, true),
+            (CsgOperation::Difference, false, false, true, false),
+            (CsgOperation::Difference, false, false, false, false),
+        ];
+        for (operation, lhit, inl, inr, result) in rules {
+            assert_eq!(operation.intersection_allowed(lhit, inl, inr), result, "{:?} {} {} {}", operation, lhit, inl, inr);
+        }
+    }
+
+    #[test]
+    fn filtering_a_list_of_intersections() {
+        let cases = [
+            (CsgOperation::Union, 0, 3),
+            (CsgOperation::Intersection, 1, 2),
+            (CsgOperation::Difference, 0, 1),
+        ];
+        for (operation, x0, x1) in cases {
+            let c = Object::new_csg(operation, Object::new_sphere(), Object::new_cube());
+            let [s1, s2] = c.children() else { unreachable!() };
+            let xs = Intersections::new().with_intersections(vec![
+                Intersection::new(1.0, s1),
+                Intersection::new(2.0, s2),
+                Intersection::new(3.0, s1),
+                Intersection::new(4.0, s2),
+            ]);
+            let expected = [xs[x0].clone(), xs[x1].clone()];
+            let result = csg(&c).filter_intersections(xs);
+            assert_eq!(result.count(), 2);
+            assert_eq!(result[0], expected[0]);
+            assert_eq!(result[1], expected[1]);
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_csg_object() {
+        let c = Object::new_csg(CsgOperation::Union, Object::new_sphere(), Object::new_cube());
+        let r = Ray::new(dvec3(0.0, 2.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        assert_eq!(xs.count(), 0);
+    }
+
+    #[test]
+    fn a_ray_hits_a_csg_object() {
+        let s1 = Object::new_sphere();
+        let s2 = Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(0.0, 0.0, 0.5)));
+        let c = Object::new_csg(CsgOperation::Union, s1, s2);
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        assert_eq!(xs.count(), 2);
+        assert_eq!(xs[0].t(), 4.0);
+        assert!(std::ptr::eq(xs[0].object(), csg(&c).left()));
+        assert_eq!(xs[1].t(), 6.5);
+        assert!(std::ptr::eq(xs[1].object(), csg(&c).right()));
+    }
+
+    #[test]
+    fn a_difference_drills_a_hole_through_a_cube() {
+        let cube = Object::new_cube();
+        let drill = Object::new(Shape::Cylinder(Cylinder { min: -2.0, max: 2.0, closed: true }))
+            .with_transform(Transform::from_scale(dvec3(0.5, 1.0, 0.5)));
+        let c = Object::new_csg(CsgOperation::Difference, cube, drill)
+            .with_transform(Transform::new().with_rotation_x(std::f64::consts::FRAC_PI_2));
+        // Along the axis of the drill, turned toward z by the csg transform
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(c.intersect(&r).count(), 0);
+        // Through the wall of the cube, next to the hole
+        let r = Ray::new(dvec3(0.75, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        assert_eq!(xs.count(), 2);
+        assert!((xs[0].t() - 4.0).abs() < 1e-6);
+        assert!(xs[0].object().normal_at(dvec3(0.75, 0.0, -1.0)).abs_diff_eq(dvec3(0.0, 0.0, -1.0), 1e-6));
+    }
+}
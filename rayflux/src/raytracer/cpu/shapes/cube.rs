@@ -1,4 +1,4 @@
-use glam::DVec3;
+use glam::{DVec2, DVec3};
 
 use crate::{common::{shapes::Cube, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray}};
 
@@ -45,7 +45,7 @@ impl HittableShape for Cube {
         }
     }
     
-    fn normal_at<'a>(&self, point: DVec3) -> DVec3 {
+    fn normal_at(&self, point: DVec3) -> DVec3 {
         let maxc = f64::max(point.x.abs(), f64::max(point.y.abs(), point.z.abs()));
         if maxc == point.x.abs() { 
             DVec3::new(point.x, 0.0, 0.0)
@@ -55,10 +55,127 @@ impl HittableShape for Cube {
             DVec3::new(0.0, 0.0, point.z)
         }
     }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        // Faces are laid out as a horizontal cross:
+        //        up
+        // left front right back
+        //       down
+        let face = Cube::face_from_point(point);
+        let (column, row) = match face {
+            CubeFace::Left => (0.0, 1.0),
+            CubeFace::Front => (1.0, 1.0),
+            CubeFace::Right => (2.0, 1.0),
+            CubeFace::Back => (3.0, 1.0),
+            CubeFace::Up => (1.0, 2.0),
+            CubeFace::Down => (1.0, 0.0),
+        };
+        let uv = Cube::face_uv(face, point);
+        DVec2::new((column + uv.x) / 4.0, (row + uv.y) / 3.0)
+    }
+}
+
+/// The six faces of a cube, front being the +z face
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Right,
+    Front,
+    Back,
+    Up,
+    Down,
 }
 
 impl Cube {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Find the face a point lies on
+    pub fn face_from_point(point: DVec3) -> CubeFace {
+        let maxc = f64::max(point.x.abs(), f64::max(point.y.abs(), point.z.abs()));
+        if maxc == point.x {
+            CubeFace::Right
+        } else if maxc == -point.x {
+            CubeFace::Left
+        } else if maxc == point.y {
+            CubeFace::Up
+        } else if maxc == -point.y {
+            CubeFace::Down
+        } else if maxc == point.z {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+
+    /// Texture coordinates of a point on a face, in [0, 1] for the face alone
+    pub fn face_uv(face: CubeFace, point: DVec3) -> DVec2 {
+        let (u, v) = match face {
+            CubeFace::Front => (point.x + 1.0, point.y + 1.0),
+            CubeFace::Back => (1.0 - point.x, point.y + 1.0),
+            CubeFace::Left => (point.z + 1.0, point.y + 1.0),
+            CubeFace::Right => (1.0 - point.z, point.y + 1.0),
+            CubeFace::Up => (point.x + 1.0, 1.0 - point.z),
+            CubeFace::Down => (point.x + 1.0, point.z + 1.0),
+        };
+        DVec2::new(u.rem_euclid(2.0) / 2.0, v.rem_euclid(2.0) / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{dvec2, dvec3};
+
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    #[test]
+    fn identifying_the_face_of_a_cube_from_a_point() {
+        let examples = [
+            (dvec3(-1.0, 0.5, -0.25), CubeFace::Left),
+            (dvec3(1.1, -0.75, 0.8), CubeFace::Right),
+            (dvec3(0.1, 0.6, 0.9), CubeFace::Front),
+            (dvec3(-0.7, 0.0, -2.0), CubeFace::Back),
+            (dvec3(0.5, 1.0, 0.9), CubeFace::Up),
+            (dvec3(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+        for (point, face) in examples {
+            assert_eq!(Cube::face_from_point(point), face);
+        }
+    }
+
+    #[test]
+    fn uv_mapping_the_faces_of_a_cube() {
+        let examples = [
+            (CubeFace::Front, dvec3(-0.5, 0.5, 1.0), dvec2(0.25, 0.75)),
+            (CubeFace::Front, dvec3(0.5, -0.5, 1.0), dvec2(0.75, 0.25)),
+            (CubeFace::Back, dvec3(0.5, 0.5, -1.0), dvec2(0.25, 0.75)),
+            (CubeFace::Back, dvec3(-0.5, -0.5, -1.0), dvec2(0.75, 0.25)),
+            (CubeFace::Left, dvec3(-1.0, 0.5, -0.5), dvec2(0.25, 0.75)),
+            (CubeFace::Left, dvec3(-1.0, -0.5, 0.5), dvec2(0.75, 0.25)),
+            (CubeFace::Right, dvec3(1.0, 0.5, 0.5), dvec2(0.25, 0.75)),
+            (CubeFace::Right, dvec3(1.0, -0.5, -0.5), dvec2(0.75, 0.25)),
+            (CubeFace::Up, dvec3(-0.5, 1.0, -0.5), dvec2(0.25, 0.75)),
+            (CubeFace::Up, dvec3(0.5, 1.0, 0.5), dvec2(0.75, 0.25)),
+            (CubeFace::Down, dvec3(-0.5, -1.0, 0.5), dvec2(0.25, 0.75)),
+            (CubeFace::Down, dvec3(0.5, -1.0, -0.5), dvec2(0.75, 0.25)),
+        ];
+        for (face, point, uv) in examples {
+            assert!(Cube::face_uv(face, point).abs_diff_eq(uv, EPSILON));
+        }
+    }
+
+    #[test]
+    fn cube_faces_are_laid_out_as_a_cross() {
+        let c = Cube::new();
+        // Center of each face
+        assert!(c.uv_at(dvec3(0.0, 0.0, 1.0)).abs_diff_eq(dvec2(1.5 / 4.0, 1.5 / 3.0), EPSILON));
+        assert!(c.uv_at(dvec3(-1.0, 0.0, 0.0)).abs_diff_eq(dvec2(0.5 / 4.0, 1.5 / 3.0), EPSILON));
+        assert!(c.uv_at(dvec3(1.0, 0.0, 0.0)).abs_diff_eq(dvec2(2.5 / 4.0, 1.5 / 3.0), EPSILON));
+        assert!(c.uv_at(dvec3(0.0, 0.0, -1.0)).abs_diff_eq(dvec2(3.5 / 4.0, 1.5 / 3.0), EPSILON));
+        assert!(c.uv_at(dvec3(0.0, 1.0, 0.0)).abs_diff_eq(dvec2(1.5 / 4.0, 2.5 / 3.0), EPSILON));
+        assert!(c.uv_at(dvec3(0.0, -1.0, 0.0)).abs_diff_eq(dvec2(1.5 / 4.0, 0.5 / 3.0), EPSILON));
+    }
 }
@@ -1,6 +1,6 @@
-use std::f64::EPSILON;
+use std::f64::consts::PI;
 
-use glam::DVec3;
+use glam::{DVec2, DVec3};
 
 use crate::{common::{shapes::Cylinder, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray}};
 
@@ -77,14 +77,60 @@ impl HittableShape for Cylinder {
         intersections
     }
     
-    fn normal_at<'a>(&self, point: DVec3) -> DVec3 {
+    fn normal_at(&self, point: DVec3) -> DVec3 {
         let dist = point.x * point.x + point.z * point.z;
-        if dist < 1.0 && point.y >= self.max - EPSILON {
+        if dist < 1.0 && point.y >= self.max - f64::EPSILON {
             DVec3::new(0.0, 1.0, 0.0)
-        } else if dist < 1.0 && point.y <= self.min + EPSILON {
+        } else if dist < 1.0 && point.y <= self.min + f64::EPSILON {
             DVec3::new(0.0, -1.0, 0.0)
         } else {
             DVec3::new(point.x, 0.0, point.z)
         }
     }
-}
\ No newline at end of file
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        let dist = point.x * point.x + point.z * point.z;
+        if dist < 1.0 - 1.0e-6 {
+            // Points strictly inside the unit circle lie on a cap, mapped as a disk
+            DVec2::new((point.x + 1.0) / 2.0, (1.0 - point.z) / 2.0)
+        } else {
+            let theta = point.x.atan2(point.z);
+            let raw_u = theta / (2.0 * PI);
+            let u = 1.0 - (raw_u + 0.5);
+            // Not wrapped, the texture repeats every unit along the axis
+            DVec2::new(u, point.y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{dvec2, dvec3};
+
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    #[test]
+    fn using_a_cylindrical_mapping_on_a_3d_point() {
+        let c = Cylinder::new();
+        let examples = [
+            (dvec3(0.0, 0.0, -1.0), dvec2(0.0, 0.0)),
+            (dvec3(0.0, 0.5, -1.0), dvec2(0.0, 0.5)),
+            (dvec3(2.0_f64.sqrt() / 2.0, 0.5, -2.0_f64.sqrt() / 2.0), dvec2(0.125, 0.5)),
+            (dvec3(1.0, 0.5, 0.0), dvec2(0.25, 0.5)),
+            (dvec3(0.0, -0.25, 1.0), dvec2(0.5, -0.25)),
+            (dvec3(-1.0, 1.25, 0.0), dvec2(0.75, 1.25)),
+        ];
+        for (point, uv) in examples {
+            assert!(c.uv_at(point).abs_diff_eq(uv, EPSILON));
+        }
+    }
+
+    #[test]
+    fn caps_are_mapped_as_disks() {
+        let c = Cylinder::new();
+        assert!(c.uv_at(dvec3(0.0, 1.0, 0.0)).abs_diff_eq(dvec2(0.5, 0.5), EPSILON));
+        assert!(c.uv_at(dvec3(0.5, 1.0, -0.5)).abs_diff_eq(dvec2(0.75, 0.75), EPSILON));
+    }
+}
@@ -0,0 +1,103 @@
+use std::f64::consts::PI;
+
+use glam::{DVec2, DVec3};
+
+use crate::{common::{shapes::Disk, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray}};
+
+impl HittableShape for Disk {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        let mut xs = Intersections::new();
+        if ray.direction.y.abs() <= f64::EPSILON {
+            return xs;
+        }
+
+        let t = -ray.origin.y / ray.direction.y;
+        let point = ray.at(t);
+        let distance2 = point.x * point.x + point.z * point.z;
+        if distance2 <= self.radius * self.radius && distance2 >= self.inner_radius * self.inner_radius {
+            xs.push(Intersection::new(t, object));
+        }
+        xs
+    }
+
+    fn normal_at(&self, _point: DVec3) -> DVec3 {
+        DVec3::new(0.0, 1.0, 0.0)
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        // Angle around the center like on a sphere, then distance from the inner to the outer edge
+        let theta = point.x.atan2(point.z);
+        let u = 1.0 - (theta / (2.0 * PI) + 0.5);
+        let distance = (point.x * point.x + point.z * point.z).sqrt();
+        let v = (distance - self.inner_radius) / (self.radius - self.inner_radius);
+        DVec2::new(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{dvec2, dvec3};
+    use crate::{common::{shapes::Shape, Transform}, raytracer::cpu::intersections::Hittable};
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    #[test]
+    fn a_ray_intersects_a_disk() {
+        let d = Object::new_disk(2.0);
+        let r = Ray::new(dvec3(1.0, 1.0, 1.0), dvec3(0.0, -1.0, 0.0));
+        let xs = d.intersect(&r);
+        assert_eq!(xs.count(), 1);
+        assert_eq!(xs[0].t(), 1.0);
+    }
+
+    #[test]
+    fn a_ray_misses_a_disk() {
+        let d = Object::new_disk(1.0);
+        let examples = [
+            (dvec3(1.0, 1.0, 1.0), dvec3(0.0, -1.0, 0.0)),
+            (dvec3(0.0, 1.0, 0.0), dvec3(1.0, 0.0, 0.0)),
+            (dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0)),
+        ];
+        for (origin, direction) in examples {
+            assert_eq!(d.intersect(&Ray::new(origin, direction)).count(), 0);
+        }
+    }
+
+    #[test]
+    fn a_ray_passes_through_the_hole_of_an_annulus() {
+        let d = Object::new(Shape::Disk(Disk::new(1.0).with_inner_radius(0.5)));
+        let r = Ray::new(dvec3(0.2, 1.0, 0.2), dvec3(0.0, -1.0, 0.0));
+        assert_eq!(d.intersect(&r).count(), 0);
+        let r = Ray::new(dvec3(0.6, 1.0, 0.0), dvec3(0.0, -1.0, 0.0));
+        assert_eq!(d.intersect(&r).count(), 1);
+    }
+
+    #[test]
+    fn the_normal_of_a_transformed_disk() {
+        let d = Object::new_disk(1.0)
+            .with_transform(Transform::new().with_rotation_x(std::f64::consts::FRAC_PI_2));
+        let n = d.normal_at(dvec3(0.0, 0.5, 0.0));
+        assert!(n.abs_diff_eq(dvec3(0.0, 0.0, 1.0), EPSILON));
+    }
+
+    #[test]
+    fn using_a_polar_mapping_on_a_disk() {
+        let d = Disk::new(2.0).with_inner_radius(1.0);
+        let examples = [
+            (dvec3(0.0, 0.0, -1.0), dvec2(0.0, 0.0)),
+            (dvec3(1.5, 0.0, 0.0), dvec2(0.25, 0.5)),
+            (dvec3(0.0, 0.0, 2.0), dvec2(0.5, 1.0)),
+        ];
+        for (point, uv) in examples {
+            assert!(d.uv_at(point).abs_diff_eq(uv, EPSILON));
+        }
+    }
+
+    #[test]
+    fn a_disk_has_a_bounding_box() {
+        let bb = Disk::new(2.0).bounds();
+        assert_eq!(bb.min(), dvec3(-2.0, 0.0, -2.0));
+        assert_eq!(bb.max(), dvec3(2.0, 0.0, 2.0));
+    }
+}
@@ -0,0 +1,135 @@
+use glam::{DVec2, DVec3};
+
+use crate::{common::{shapes::Group, Object}, raytracer::cpu::{intersections::{HittableShape, Intersections}, Ray}};
+
+impl HittableShape for Group {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        // The children are borrowed from the object to report them as the intersected objects
+        self.bvh().intersect(ray, object.children()).sort()
+    }
+
+    fn normal_at(&self, _point: DVec3) -> DVec3 {
+        unreachable!("Intersections are reported on the children of a group, never on the group itself")
+    }
+
+    fn uv_at(&self, _point: DVec3) -> DVec2 {
+        unreachable!("Intersections are reported on the children of a group, never on the group itself")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use glam::dvec3;
+    use crate::{common::{shapes::Shape, Rng, Transform}, raytracer::cpu::intersections::Hittable};
+    use super::*;
+
+    const EPSILON: f64 = 0.0001;
+
+    #[test]
+    fn intersecting_a_ray_with_an_empty_group() {
+        let g = Object::new_group(vec![]);
+        let r = Ray::new(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.count(), 0);
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_nonempty_group() {
+        let s1 = Object::new_sphere();
+        let s2 = Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(0.0, 0.0, -3.0)));
+        let s3 = Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(5.0, 0.0, 0.0)));
+        let g = Object::new_group(vec![s1, s2, s3]);
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.count(), 4);
+        assert!(std::ptr::eq(xs[0].object(), &g.children()[1]));
+        assert!(std::ptr::eq(xs[1].object(), &g.children()[1]));
+        assert!(std::ptr::eq(xs[2].object(), &g.children()[0]));
+        assert!(std::ptr::eq(xs[3].object(), &g.children()[0]));
+    }
+
+    #[test]
+    fn intersecting_a_transformed_group() {
+        let s = Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(5.0, 0.0, 0.0)));
+        let g = Object::new_group(vec![s])
+            .with_transform(Transform::from_scale(dvec3(2.0, 2.0, 2.0)));
+        let r = Ray::new(dvec3(10.0, 0.0, -10.0), dvec3(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.count(), 2);
+    }
+
+    #[test]
+    fn converting_a_point_from_world_to_object_space() {
+        let s = Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(5.0, 0.0, 0.0)));
+        let g2 = Object::new_group(vec![s])
+            .with_transform(Transform::from_scale(dvec3(2.0, 2.0, 2.0)));
+        let g1 = Object::new_group(vec![g2])
+            .with_transform(Transform::new().with_rotation_y(FRAC_PI_2));
+        let s = &g1.children()[0].children()[0];
+        let p = s.world_to_object(dvec3(-2.0, 0.0, -10.0));
+        assert!(p.abs_diff_eq(dvec3(0.0, 0.0, -1.0), EPSILON));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_child_object() {
+        let s = Object::new_sphere()
+            .with_transform(Transform::from_translation(dvec3(5.0, 0.0, 0.0)));
+        let g2 = Object::new_group(vec![s])
+            .with_transform(Transform::from_scale(dvec3(1.0, 2.0, 3.0)));
+        let g1 = Object::new_group(vec![g2])
+            .with_transform(Transform::new().with_rotation_y(FRAC_PI_2));
+        let s = &g1.children()[0].children()[0];
+        let n = s.normal_at(dvec3(1.7321, 1.1547, -5.5774));
+        assert!(n.abs_diff_eq(dvec3(0.2857, 0.4286, -0.8571), EPSILON));
+    }
+
+    #[test]
+    fn children_follow_a_transform_updated_after_being_added() {
+        let s = Object::new_sphere();
+        let mut g = Object::new_group(vec![s]);
+        let mut transform = *g.transform();
+        transform.translate(dvec3(0.0, 0.0, 10.0));
+        g.set_transform(transform);
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.count(), 2);
+        assert!((xs[0].t() - 14.0).abs() < EPSILON);
+        assert!(g.children()[0].normal_at(dvec3(0.0, 0.0, 9.0)).abs_diff_eq(dvec3(0.0, 0.0, -1.0), EPSILON));
+    }
+
+    #[test]
+    fn a_large_group_only_tests_the_children_along_the_ray() {
+        // A 40x40 grid of triangles in the xz plane
+        let mut triangles = Vec::new();
+        for x in 0..40 {
+            for z in 0..40 {
+                let p = dvec3(x as f64, 0.0, z as f64);
+                triangles.push(Object::new_triangle(p, p + dvec3(1.0, 0.0, 0.0), p + dvec3(0.0, 0.0, 1.0)));
+            }
+        }
+        let g = Object::new_group(triangles)
+            .with_transform(Transform::from_translation(dvec3(-20.0, 0.0, -20.0)));
+        let Shape::Group(group) = g.shape() else {
+            unreachable!()
+        };
+
+        let mut rng = Rng::new(5);
+        for _ in 0..100 {
+            let target = dvec3(rng.next_f64() * 40.0 - 20.0, 0.0, rng.next_f64() * 40.0 - 20.0);
+            let r = Ray::new(dvec3(0.0, 10.0, 0.0), target - dvec3(0.0, 10.0, 0.0));
+            let local_ray = r.transform(&g.transform().inverse_matrix());
+            let expected = g.children().iter().filter(|child| child.intersect(&local_ray).count() > 0).count();
+            assert_eq!(g.intersect(&r).count(), expected);
+
+            let mut tested = 0;
+            group.bvh().for_each_candidate(&local_ray, |_| tested += 1);
+            assert!(tested <= 16, "{} children tested", tested);
+        }
+    }
+}
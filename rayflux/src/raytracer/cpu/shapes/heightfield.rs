@@ -0,0 +1,294 @@
+use glam::{DVec2, DVec3};
+
+use crate::{common::{shapes::{Heightfield, Triangle}, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray, EPSILON}};
+
+impl Heightfield {
+    /// Number of cells along x and z
+    fn cells(&self) -> (i64, i64) {
+        (self.width() as i64 - 1, self.depth() as i64 - 1)
+    }
+
+    fn sample_point(&self, x: i64, z: i64) -> DVec3 {
+        let (cells_x, cells_z) = self.cells();
+        DVec3::new(x as f64 / cells_x as f64, self.height(x, z), z as f64 / cells_z as f64)
+    }
+
+    /// The two triangles covering a cell, split along its diagonal
+    fn cell_triangles(&self, x: i64, z: i64) -> [Triangle; 2] {
+        let p00 = self.sample_point(x, z);
+        let p10 = self.sample_point(x + 1, z);
+        let p01 = self.sample_point(x, z + 1);
+        let p11 = self.sample_point(x + 1, z + 1);
+        [Triangle::new(p00, p10, p11), Triangle::new(p00, p11, p01)]
+    }
+
+    /// Cell containing a point and the position of the point inside it, in [0, 1]
+    fn cell_at(&self, point: DVec3) -> (i64, i64, DVec2) {
+        let (cells_x, cells_z) = self.cells();
+        let fx = point.x * cells_x as f64;
+        let fz = point.z * cells_z as f64;
+        let x = (fx.floor() as i64).clamp(0, cells_x - 1);
+        let z = (fz.floor() as i64).clamp(0, cells_z - 1);
+        (x, z, DVec2::new((fx - x as f64).clamp(0.0, 1.0), (fz - z as f64).clamp(0.0, 1.0)))
+    }
+
+    /// Normal of a sample, from the slopes toward its neighbours
+    fn sample_normal(&self, x: i64, z: i64) -> DVec3 {
+        let (cells_x, cells_z) = self.cells();
+        let (left, right) = ((x - 1).max(0), (x + 1).min(cells_x));
+        let (back, front) = ((z - 1).max(0), (z + 1).min(cells_z));
+        let slope_x = (self.height(right, z) - self.height(left, z)) * cells_x as f64 / (right - left) as f64;
+        let slope_z = (self.height(x, front) - self.height(x, back)) * cells_z as f64 / (front - back) as f64;
+        DVec3::new(-slope_x, 1.0, -slope_z).normalize()
+    }
+}
+
+impl HittableShape for Heightfield {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        let mut xs = Intersections::new();
+        let Some((tmin, tmax)) = self.bounds().intersect(ray) else {
+            return xs;
+        };
+
+        // 2D DDA over the cells crossed by the ray, seen from above
+        let (cells_x, cells_z) = self.cells();
+        let start = ray.at(tmin);
+        let (mut x, mut z, _) = self.cell_at(start);
+
+        let step_x = if ray.direction.x > 0.0 { 1 } else { -1 };
+        let step_z = if ray.direction.z > 0.0 { 1 } else { -1 };
+        let delta_x = 1.0 / (cells_x as f64 * ray.direction.x.abs());
+        let delta_z = 1.0 / (cells_z as f64 * ray.direction.z.abs());
+        let boundary = |cell: i64, step: i64, cells: i64| (cell + (step > 0) as i64) as f64 / cells as f64;
+        let mut next_x = if ray.direction.x == 0.0 {
+            f64::INFINITY
+        } else {
+            (boundary(x, step_x, cells_x) - ray.origin.x) / ray.direction.x
+        };
+        let mut next_z = if ray.direction.z == 0.0 {
+            f64::INFINITY
+        } else {
+            (boundary(z, step_z, cells_z) - ray.origin.z) / ray.direction.z
+        };
+
+        let mut enter = tmin;
+        loop {
+            let exit = next_x.min(next_z).min(tmax);
+
+            // Skip the cells the ray passes above or below
+            let (y0, y1) = (ray.at(enter).y, ray.at(exit).y);
+            let corners = [self.height(x, z), self.height(x + 1, z), self.height(x, z + 1), self.height(x + 1, z + 1)];
+            let low = corners.iter().copied().fold(f64::INFINITY, f64::min);
+            let high = corners.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            if y0.min(y1) <= high + EPSILON && y0.max(y1) >= low - EPSILON {
+                let mut hits: Vec<f64> = self
+                    .cell_triangles(x, z)
+                    .iter()
+                    .filter_map(|triangle| triangle.hit(ray).map(|(t, _, _)| t))
+                    .collect();
+                hits.sort_by(f64::total_cmp);
+                for t in hits {
+                    // A hit on a shared edge is found by both of its triangles or cells
+                    if xs.get_all().last().is_none_or(|last| t - last.t() > EPSILON) {
+                        xs.push(Intersection::new(t, object));
+                    }
+                }
+            }
+
+            if exit >= tmax {
+                break;
+            }
+            enter = exit;
+            if next_x < next_z {
+                x += step_x;
+                next_x += delta_x;
+            } else {
+                z += step_z;
+                next_z += delta_z;
+            }
+            if x < 0 || x >= cells_x || z < 0 || z >= cells_z {
+                break;
+            }
+        }
+        xs
+    }
+
+    fn normal_at(&self, point: DVec3) -> DVec3 {
+        // Flat normal of the triangle under the point
+        let (x, z, local) = self.cell_at(point);
+        let [first, second] = self.cell_triangles(x, z);
+        if local.x >= local.y { first.normal() } else { second.normal() }
+    }
+
+    fn normal_at_hit(&self, point: DVec3, _hit: &Intersection) -> DVec3 {
+        // Bilinear interpolation of the normals of the cell corners
+        let (x, z, local) = self.cell_at(point);
+        let n00 = self.sample_normal(x, z);
+        let n10 = self.sample_normal(x + 1, z);
+        let n01 = self.sample_normal(x, z + 1);
+        let n11 = self.sample_normal(x + 1, z + 1);
+        let back = n00.lerp(n10, local.x);
+        let front = n01.lerp(n11, local.x);
+        back.lerp(front, local.y).normalize()
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        // Matches the image the heights were loaded from, its top row being at z = 0
+        DVec2::new(point.x, 1.0 - point.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+    use crate::{common::{shapes::Shape, Rng}, raytracer::cpu::intersections::Hittable};
+    use super::*;
+
+    fn bumpy(size: usize, rng: &mut Rng) -> Heightfield {
+        Heightfield::new(size, size, (0..size * size).map(|_| rng.next_f64()).collect())
+    }
+
+    #[test]
+    fn a_ray_hits_a_flat_heightfield() {
+        let h = Object::new_heightfield(Heightfield::new(3, 3, vec![0.5; 9]));
+        let r = Ray::new(dvec3(0.3, 2.0, 0.7), dvec3(0.0, -1.0, 0.0));
+        let xs = h.intersect(&r);
+        assert_eq!(xs.count(), 1);
+        assert!((xs[0].t() - 1.5).abs() < EPSILON);
+        assert!(h.normal_at(r.at(1.5)).abs_diff_eq(dvec3(0.0, 1.0, 0.0), EPSILON));
+    }
+
+    #[test]
+    fn a_ray_hits_a_finely_sampled_heightfield() {
+        // Cells of a large grid are tiny, the triangle test must not take them for parallel to the ray
+        let size = 1025;
+        let h = Object::new_heightfield(Heightfield::new(size, size, vec![0.5; size * size]));
+        let r = Ray::new(dvec3(0.3, 2.0, 0.7), dvec3(0.0, -1.0, 0.0));
+        let xs = h.intersect(&r);
+        assert_eq!(xs.count(), 1);
+        assert!((xs[0].t() - 1.5).abs() < EPSILON);
+
+        let r = Ray::new(dvec3(-1.0, 1.5, 0.3), dvec3(1.0, -1.0, 0.2));
+        let xs = h.intersect(&r);
+        assert_eq!(xs.count(), 1);
+        assert!((xs[0].t() - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn a_ray_misses_the_heightfield_outside_the_unit_square() {
+        let h = Object::new_heightfield(Heightfield::new(3, 3, vec![0.5; 9]));
+        let r = Ray::new(dvec3(1.3, 2.0, 0.5), dvec3(0.0, -1.0, 0.0));
+        assert_eq!(h.intersect(&r).count(), 0);
+        let r = Ray::new(dvec3(-1.0, 0.6, 0.5), dvec3(1.0, 0.0, 0.0));
+        assert_eq!(h.intersect(&r).count(), 0);
+    }
+
+    #[test]
+    fn a_horizontal_ray_crosses_every_ridge() {
+        // Heights alternating along x, constant along z
+        let heights = (0..5).flat_map(|_| [0.0, 1.0, 0.0, 1.0, 0.0]).collect();
+        let h = Object::new_heightfield(Heightfield::new(5, 5, heights));
+        let r = Ray::new(dvec3(-1.0, 0.5, 0.4), dvec3(1.0, 0.0, 0.0));
+        let ts: Vec<f64> = h.intersect(&r).get_all().iter().map(|i| i.t()).collect();
+        let expected = [1.125, 1.375, 1.625, 1.875];
+        assert_eq!(ts.len(), expected.len());
+        for (t, expected) in ts.iter().zip(expected) {
+            assert!((t - expected).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn the_traversal_finds_the_same_hits_as_every_triangle() {
+        let mut rng = Rng::new(9);
+        let heightfield = bumpy(17, &mut rng);
+        let triangles: Vec<Triangle> = (0..16)
+            .flat_map(|z| (0..16).map(move |x| (x, z)))
+            .flat_map(|(x, z)| heightfield.cell_triangles(x, z))
+            .collect();
+        let h = Object::new_heightfield(heightfield);
+
+        for _ in 0..300 {
+            let origin = dvec3(rng.next_f64() * 3.0 - 1.0, rng.next_f64() * 2.0 - 0.5, rng.next_f64() * 3.0 - 1.0);
+            let target = dvec3(rng.next_f64(), rng.next_f64(), rng.next_f64());
+            let r = Ray::new(origin, target - origin);
+
+            let mut expected: Vec<f64> = triangles.iter().filter_map(|t| t.hit(&r).map(|(t, _, _)| t)).collect();
+            expected.sort_by(f64::total_cmp);
+            expected.dedup_by(|a, b| (*a - *b).abs() <= EPSILON);
+
+            let ts: Vec<f64> = h.intersect(&r).get_all().iter().map(|i| i.t()).collect();
+            assert_eq!(ts.len(), expected.len(), "{:?} {:?}", ts, expected);
+            for (t, expected) in ts.iter().zip(expected) {
+                assert!((t - expected).abs() < EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn normals_are_interpolated_across_the_cells() {
+        let mut rng = Rng::new(4);
+        let h = bumpy(5, &mut rng);
+        let hit_object = Object::new_sphere();
+        let hit = Intersection::new(0.0, &hit_object);
+
+        // On a sample, the normal is the one of the sample
+        let n = h.normal_at_hit(dvec3(0.5, 0.0, 0.25), &hit);
+        assert!(n.abs_diff_eq(h.sample_normal(2, 1), EPSILON));
+
+        // Continuous when crossing a cell boundary
+        let a = h.normal_at_hit(dvec3(0.25 - 1e-9, 0.0, 0.6), &hit);
+        let b = h.normal_at_hit(dvec3(0.25 + 1e-9, 0.0, 0.6), &hit);
+        assert!(a.abs_diff_eq(b, 1e-6));
+    }
+
+    #[test]
+    fn the_normal_of_a_slope() {
+        // Rising by 1 along x over the unit square
+        let h = Heightfield::new(3, 2, vec![0.0, 0.5, 1.0, 0.0, 0.5, 1.0]);
+        let expected = dvec3(-1.0, 1.0, 0.0).normalize();
+        let hit_object = Object::new_sphere();
+        let hit = Intersection::new(0.0, &hit_object);
+        assert!(h.normal_at(dvec3(0.3, 0.3, 0.5)).abs_diff_eq(expected, EPSILON));
+        assert!(h.normal_at_hit(dvec3(0.3, 0.3, 0.5), &hit).abs_diff_eq(expected, EPSILON));
+    }
+
+    #[test]
+    fn a_heightfield_has_a_bounding_box() {
+        let h = Heightfield::new(2, 2, vec![0.25, 0.5, 0.75, 0.3]);
+        let bb = Shape::Heightfield(h).bounds();
+        assert_eq!(bb.min(), dvec3(0.0, 0.25, 0.0));
+        assert_eq!(bb.max(), dvec3(1.0, 0.75, 1.0));
+    }
+
+    #[test]
+    fn loading_a_heightfield_from_a_grayscale_image() {
+        let path = std::env::temp_dir().join("rayflux_heightfield_test.png");
+        image::GrayImage::from_raw(3, 2, vec![0, 51, 255, 102, 153, 204])
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        let h = Heightfield::load(&path).unwrap();
+        assert_eq!(h.width(), 3);
+        assert_eq!(h.depth(), 2);
+        assert_eq!(h.height(0, 0), 0.0);
+        assert!((h.height(1, 0) - 0.2).abs() < EPSILON);
+        assert_eq!(h.height(2, 0), 1.0);
+        assert!((h.height(0, 1) - 0.4).abs() < EPSILON);
+        assert_eq!(h.min_height(), 0.0);
+        assert_eq!(h.max_height(), 1.0);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn loading_an_image_too_small_for_a_heightfield_fails() {
+        let path = std::env::temp_dir().join("rayflux_heightfield_too_small_test.png");
+        image::GrayImage::from_raw(3, 1, vec![0, 128, 255])
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        assert!(matches!(Heightfield::load(&path), Err(image::ImageError::Parameter(_))));
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,15 +1,20 @@
-use crate::{common::{shapes::Shape, Object}, raytracer::cpu::{intersections::{HittableShape, Intersections}, Ray}};
+use crate::{common::{shapes::Shape, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray}};
 
 pub mod sphere;
 pub mod plane;
 pub mod cube;
 pub mod cylinder;
+pub mod cone;
+pub mod torus;
+pub mod disk;
+pub mod quad;
+pub mod sdf;
+pub mod heightfield;
+pub mod triangle;
+pub mod group;
+pub mod csg;
 
-use glam::DVec3;
-pub use sphere::*;
-pub use plane::*;
-pub use cube::*;
-pub use cylinder::*;
+use glam::{DVec2, DVec3};
 
 impl HittableShape for Shape {
     fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
@@ -18,15 +23,62 @@ impl HittableShape for Shape {
             Shape::Plane(plane) => plane.intersect(ray, object),
             Shape::Cube(cube) => cube.intersect(ray, object),
             Shape::Cylinder(cylinder) => cylinder.intersect(ray, object),
+            Shape::Cone(cone) => cone.intersect(ray, object),
+            Shape::Torus(torus) => torus.intersect(ray, object),
+            Shape::Disk(disk) => disk.intersect(ray, object),
+            Shape::Quad(quad) => quad.intersect(ray, object),
+            Shape::Sdf(sdf) => sdf.intersect(ray, object),
+            Shape::Heightfield(heightfield) => heightfield.intersect(ray, object),
+            Shape::Triangle(triangle) => triangle.intersect(ray, object),
+            Shape::SmoothTriangle(triangle) => triangle.intersect(ray, object),
+            Shape::Group(group) => group.intersect(ray, object),
+            Shape::Csg(csg) => csg.intersect(ray, object),
         }
     }
     
-    fn normal_at<'a>(&self, point: DVec3) -> DVec3 {
+    fn normal_at(&self, point: DVec3) -> DVec3 {
         match self {
             Shape::Sphere(sphere) => sphere.normal_at(point),
             Shape::Plane(plane) => plane.normal_at(point),
             Shape::Cube(cube) => cube.normal_at(point),
             Shape::Cylinder(cylinder) => cylinder.normal_at(point),
+            Shape::Cone(cone) => cone.normal_at(point),
+            Shape::Torus(torus) => torus.normal_at(point),
+            Shape::Disk(disk) => disk.normal_at(point),
+            Shape::Quad(quad) => quad.normal_at(point),
+            Shape::Sdf(sdf) => sdf.normal_at(point),
+            Shape::Heightfield(heightfield) => heightfield.normal_at(point),
+            Shape::Triangle(triangle) => triangle.normal_at(point),
+            Shape::SmoothTriangle(triangle) => triangle.normal_at(point),
+            Shape::Group(group) => group.normal_at(point),
+            Shape::Csg(csg) => csg.normal_at(point),
+        }
+    }
+
+    fn normal_at_hit(&self, point: DVec3, hit: &Intersection) -> DVec3 {
+        match self {
+            Shape::SmoothTriangle(triangle) => triangle.normal_at_hit(point, hit),
+            Shape::Heightfield(heightfield) => heightfield.normal_at_hit(point, hit),
+            _ => self.normal_at(point),
+        }
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        match self {
+            Shape::Sphere(sphere) => sphere.uv_at(point),
+            Shape::Plane(plane) => plane.uv_at(point),
+            Shape::Cube(cube) => cube.uv_at(point),
+            Shape::Cylinder(cylinder) => cylinder.uv_at(point),
+            Shape::Cone(cone) => cone.uv_at(point),
+            Shape::Torus(torus) => torus.uv_at(point),
+            Shape::Disk(disk) => disk.uv_at(point),
+            Shape::Quad(quad) => quad.uv_at(point),
+            Shape::Sdf(sdf) => sdf.uv_at(point),
+            Shape::Heightfield(heightfield) => heightfield.uv_at(point),
+            Shape::Triangle(triangle) => triangle.uv_at(point),
+            Shape::SmoothTriangle(triangle) => triangle.uv_at(point),
+            Shape::Group(group) => group.uv_at(point),
+            Shape::Csg(csg) => csg.uv_at(point),
         }
     }
 }
\ No newline at end of file
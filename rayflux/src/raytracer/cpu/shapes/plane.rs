@@ -1,13 +1,11 @@
-use std::f64::EPSILON;
-
-use glam::DVec3;
+use glam::{DVec2, DVec3};
 
 use crate::{common::{shapes::Plane, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray}};
 
 impl HittableShape for Plane {
     fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
         let mut xs = Intersections::new();
-        if ray.direction.y.abs() > EPSILON {
+        if ray.direction.y.abs() > f64::EPSILON {
             xs.push(
                 Intersection::new(
                     -ray.origin.y / ray.direction.y,
@@ -19,9 +17,14 @@ impl HittableShape for Plane {
         xs
     }
     
-    fn normal_at<'a>(&self, point: DVec3) -> DVec3 {
+    fn normal_at(&self, _point: DVec3) -> DVec3 {
         DVec3::new(0.0, 1.0, 0.0)
     }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        // Not wrapped, so that the texture decides between tiling and clamping
+        DVec2::new(point.x, point.z)
+    }
 }
 
 impl Plane {
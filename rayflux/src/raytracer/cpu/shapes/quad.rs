@@ -0,0 +1,138 @@
+use glam::{DVec2, DVec3};
+
+use crate::{common::{shapes::Quad, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray, EPSILON}};
+
+impl Quad {
+    /// Coordinates of a point of the plane along both edges, the quad covering [0, 1] on each of them
+    pub fn coordinates(&self, point: DVec3) -> DVec2 {
+        let p = point - self.corner();
+        let d00 = self.edge1().dot(self.edge1());
+        let d01 = self.edge1().dot(self.edge2());
+        let d11 = self.edge2().dot(self.edge2());
+        let d20 = p.dot(self.edge1());
+        let d21 = p.dot(self.edge2());
+        let denom = d00 * d11 - d01 * d01;
+        DVec2::new(
+            (d11 * d20 - d01 * d21) / denom,
+            (d00 * d21 - d01 * d20) / denom,
+        )
+    }
+}
+
+impl HittableShape for Quad {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        // Same as the Möller–Trumbore triangle test, with the parallelogram bounds on the coordinates
+        let dir_cross_e2 = ray.direction.cross(self.edge2());
+        let det = self.edge1().dot(dir_cross_e2);
+        if det.abs() < EPSILON * self.edge1().length() * self.edge2().length() * ray.direction.length() {
+            return Intersections::new();
+        }
+
+        let f = 1.0 / det;
+        let corner_to_origin = ray.origin - self.corner();
+        let u = f * corner_to_origin.dot(dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return Intersections::new();
+        }
+
+        let origin_cross_e1 = corner_to_origin.cross(self.edge1());
+        let v = f * ray.direction.dot(origin_cross_e1);
+        if !(0.0..=1.0).contains(&v) {
+            return Intersections::new();
+        }
+
+        let t = f * self.edge2().dot(origin_cross_e1);
+        Intersections::new()
+        .with_intersections(vec![Intersection::new_with_uv(t, object, u, v)])
+    }
+
+    fn normal_at(&self, _point: DVec3) -> DVec3 {
+        self.normal()
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        self.coordinates(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{dvec2, dvec3};
+    use crate::raytracer::cpu::intersections::Hittable;
+    use super::*;
+
+    fn unit_quad() -> Quad {
+        Quad::new(dvec3(-1.0, 0.0, -1.0), dvec3(2.0, 0.0, 0.0), dvec3(0.0, 0.0, 2.0))
+    }
+
+    #[test]
+    fn constructing_a_quad() {
+        let q = unit_quad();
+        assert_eq!(q.corner(), dvec3(-1.0, 0.0, -1.0));
+        assert_eq!(q.normal(), dvec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn a_ray_intersects_a_tiny_quad() {
+        let q = Object::new_quad(dvec3(-1e-3, 0.0, -1e-3), dvec3(2e-3, 0.0, 0.0), dvec3(0.0, 0.0, 2e-3));
+        let r = Ray::new(dvec3(5e-4, 2.0, 5e-4), dvec3(0.0, -1.0, 0.0));
+        let xs = q.intersect(&r);
+        assert_eq!(xs.count(), 1);
+        assert!((xs[0].t() - 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn a_ray_intersects_a_quad() {
+        let q = Object::new_quad(dvec3(-1.0, 0.0, -1.0), dvec3(2.0, 0.0, 0.0), dvec3(0.0, 0.0, 2.0));
+        let r = Ray::new(dvec3(0.5, 2.0, 0.5), dvec3(0.0, -1.0, 0.0));
+        let xs = q.intersect(&r);
+        assert_eq!(xs.count(), 1);
+        assert!((xs[0].t() - 2.0).abs() < EPSILON);
+        assert!((xs[0].u() - 0.75).abs() < EPSILON);
+        assert!((xs[0].v() - 0.75).abs() < EPSILON);
+    }
+
+    #[test]
+    fn a_ray_misses_a_quad() {
+        let q = Object::new_quad(dvec3(-1.0, 0.0, -1.0), dvec3(2.0, 0.0, 0.0), dvec3(0.0, 0.0, 2.0));
+        let examples = [
+            (dvec3(1.5, 2.0, 0.0), dvec3(0.0, -1.0, 0.0)),
+            (dvec3(0.0, 2.0, -1.5), dvec3(0.0, -1.0, 0.0)),
+            (dvec3(0.0, 1.0, 0.0), dvec3(1.0, 0.0, 0.0)),
+        ];
+        for (origin, direction) in examples {
+            assert_eq!(q.intersect(&Ray::new(origin, direction)).count(), 0);
+        }
+    }
+
+    #[test]
+    fn a_skewed_quad_is_a_parallelogram() {
+        let q = Object::new_quad(dvec3(0.0, 0.0, 0.0), dvec3(1.0, 0.0, 0.0), dvec3(1.0, 1.0, 0.0));
+        // Inside the parallelogram but outside the unit square
+        let r = Ray::new(dvec3(1.5, 0.5, -1.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(q.intersect(&r).count(), 1);
+        let r = Ray::new(dvec3(0.25, 0.5, -1.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(q.intersect(&r).count(), 0);
+    }
+
+    #[test]
+    fn the_uv_of_a_quad_follows_its_edges() {
+        let q = unit_quad();
+        let examples = [
+            (dvec3(-1.0, 0.0, -1.0), dvec2(0.0, 0.0)),
+            (dvec3(1.0, 0.0, -1.0), dvec2(1.0, 0.0)),
+            (dvec3(-1.0, 0.0, 1.0), dvec2(0.0, 1.0)),
+            (dvec3(0.0, 0.0, 0.5), dvec2(0.5, 0.75)),
+        ];
+        for (point, uv) in examples {
+            assert!(q.uv_at(point).abs_diff_eq(uv, EPSILON));
+        }
+    }
+
+    #[test]
+    fn a_quad_has_a_bounding_box() {
+        let bb = Quad::new(dvec3(1.0, 0.0, 0.0), dvec3(1.0, 2.0, 0.0), dvec3(-3.0, 0.0, 1.0)).bounds();
+        assert_eq!(bb.min(), dvec3(-2.0, 0.0, 0.0));
+        assert_eq!(bb.max(), dvec3(2.0, 2.0, 1.0));
+    }
+}
@@ -0,0 +1,257 @@
+use std::f64::consts::PI;
+
+use glam::{DVec2, DVec3};
+
+use crate::{common::{shapes::SdfShape, Object, Sdf}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray}};
+
+/// Distance under which a point is considered on the surface
+const HIT_DISTANCE: f64 = 1.0e-7;
+/// Maximum number of steps along a ray, the grazing rays converging slowly
+const MAX_STEPS: usize = 512;
+/// Length traced along a ray when the bounds are infinite
+const MAX_DISTANCE: f64 = 1000.0;
+/// Offset used to compute the gradient by central differences
+const GRADIENT_STEP: f64 = 1.0e-6;
+
+impl Sdf {
+    /// Signed distance from a point to the surface, negative inside
+    pub fn distance(&self, point: DVec3) -> f64 {
+        match self {
+            Sdf::Sphere { radius } => point.length() - radius,
+            Sdf::Cuboid { half_extents } => {
+                let q = point.abs() - *half_extents;
+                q.max(DVec3::ZERO).length() + q.max_element().min(0.0)
+            },
+            Sdf::Torus { major_radius, minor_radius } => {
+                let q = DVec2::new(DVec2::new(point.x, point.z).length() - major_radius, point.y);
+                q.length() - minor_radius
+            },
+            Sdf::Capsule { a, b, radius } => {
+                let pa = point - *a;
+                let ba = *b - *a;
+                let h = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+                (pa - ba * h).length() - radius
+            },
+            Sdf::Translate(sdf, offset) => sdf.distance(point - *offset),
+            Sdf::SmoothUnion(a, b, k) => {
+                // Polynomial smooth minimum
+                let da = a.distance(point);
+                let db = b.distance(point);
+                let h = (0.5 + 0.5 * (db - da) / k).clamp(0.0, 1.0);
+                db + (da - db) * h - k * h * (1.0 - h)
+            },
+            Sdf::Subtraction(a, b) => a.distance(point).max(-b.distance(point)),
+            Sdf::Repeat(sdf, period) => {
+                let repeated = period.cmpgt(DVec3::ZERO);
+                let cell = DVec3::select(repeated, *period * (point / *period).round(), DVec3::ZERO);
+                sdf.distance(point - cell)
+            },
+        }
+    }
+
+    /// Normalized gradient of the function, using central differences
+    pub fn gradient(&self, point: DVec3) -> DVec3 {
+        let dx = DVec3::new(GRADIENT_STEP, 0.0, 0.0);
+        let dy = DVec3::new(0.0, GRADIENT_STEP, 0.0);
+        let dz = DVec3::new(0.0, 0.0, GRADIENT_STEP);
+        DVec3::new(
+            self.distance(point + dx) - self.distance(point - dx),
+            self.distance(point + dy) - self.distance(point - dy),
+            self.distance(point + dz) - self.distance(point - dz),
+        )
+        .normalize()
+    }
+}
+
+impl HittableShape for SdfShape {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        let mut xs = Intersections::new();
+        let Some((tmin, tmax)) = self.bounds().intersect(ray) else {
+            return xs;
+        };
+
+        // Distances along the ray are measured in units of its direction, which transforms can scale
+        let speed = ray.direction.length();
+        let mut t = if tmin.is_finite() { tmin } else { 0.0 };
+        let t_end = if tmax.is_finite() { tmax } else { t + MAX_DISTANCE / speed };
+
+        // Sphere tracing, every surface crossed is reported so that the inside stays well defined
+        let mut on_surface = false;
+        for _ in 0..MAX_STEPS {
+            if t > t_end {
+                break;
+            }
+            let distance = self.sdf().distance(ray.at(t)).abs();
+            if distance < HIT_DISTANCE {
+                if !on_surface {
+                    xs.push(Intersection::new(t, object));
+                    on_surface = true;
+                }
+            } else {
+                on_surface = false;
+            }
+            // Leave the surface by small steps once it is reached
+            t += distance.max(HIT_DISTANCE) / speed;
+        }
+        xs
+    }
+
+    fn normal_at(&self, point: DVec3) -> DVec3 {
+        self.sdf().gradient(point)
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        // No natural parametrization, the direction from the center is mapped like on a sphere
+        let theta = point.x.atan2(point.z);
+        let phi = (point.y / point.length()).acos();
+        DVec2::new(1.0 - (theta / (2.0 * PI) + 0.5), 1.0 - phi / PI)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+    use crate::{common::{color::Color, shapes::Shape, BoundingBox, PointLight, Rng, Scene, Transform}, raytracer::cpu::{intersections::Hittable, CpuRaytracer, DEFAULT_MAX_DEPTH}};
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    fn assert_hits(xs: &Intersections, expected: &[f64]) {
+        assert_eq!(xs.count(), expected.len());
+        for (intersection, t) in xs.get_all().iter().zip(expected) {
+            assert!((intersection.t() - t).abs() < EPSILON, "{} != {}", intersection.t(), t);
+        }
+    }
+
+    #[test]
+    fn distances_to_the_primitives() {
+        let p = dvec3(3.0, 0.0, 0.0);
+        assert_eq!(Sdf::sphere(1.0).distance(p), 2.0);
+        assert_eq!(Sdf::cuboid(dvec3(1.0, 2.0, 3.0)).distance(p), 2.0);
+        assert_eq!(Sdf::cuboid(dvec3(1.0, 2.0, 3.0)).distance(DVec3::ZERO), -1.0);
+        assert!((Sdf::cuboid(DVec3::ONE).distance(dvec3(2.0, 2.0, 1.0)) - 2.0_f64.sqrt()).abs() < EPSILON);
+        assert_eq!(Sdf::torus(1.0, 0.25).distance(p), 1.75);
+        assert_eq!(Sdf::torus(1.0, 0.25).distance(dvec3(0.0, 0.0, 1.0)), -0.25);
+        assert_eq!(Sdf::capsule(dvec3(0.0, -1.0, 0.0), dvec3(0.0, 1.0, 0.0), 0.5).distance(p), 2.5);
+        assert_eq!(Sdf::capsule(dvec3(0.0, -1.0, 0.0), dvec3(0.0, 1.0, 0.0), 0.5).distance(dvec3(0.0, 3.0, 0.0)), 1.5);
+    }
+
+    #[test]
+    fn distances_through_the_operations() {
+        let a = Sdf::sphere(1.0).translate(dvec3(-1.0, 0.0, 0.0));
+        let b = Sdf::sphere(1.0).translate(dvec3(1.0, 0.0, 0.0));
+        assert_eq!(a.distance(dvec3(2.0, 0.0, 0.0)), 2.0);
+
+        // The blend fills the gap between both spheres
+        let p = dvec3(0.0, 1.0, 0.0);
+        let hard = a.distance(p).min(b.distance(p));
+        let smooth = a.clone().smooth_union(b.clone(), 0.5).distance(p);
+        assert!(smooth < hard);
+        // Far from the blend the union is exact
+        assert!((a.clone().smooth_union(b, 0.5).distance(dvec3(5.0, 0.0, 0.0)) - 3.0).abs() < EPSILON);
+
+        let hollow = Sdf::sphere(2.0).subtract(Sdf::sphere(1.0));
+        assert_eq!(hollow.distance(DVec3::ZERO), 1.0);
+        assert_eq!(hollow.distance(dvec3(1.5, 0.0, 0.0)), -0.5);
+
+        let repeated = Sdf::sphere(1.0).repeat(dvec3(4.0, 0.0, 0.0));
+        assert_eq!(repeated.distance(dvec3(8.0, 2.0, 0.0)), 1.0);
+        assert_eq!(repeated.distance(dvec3(-6.0, 0.0, 0.0)), 1.0);
+    }
+
+    #[test]
+    fn a_ray_intersects_a_distance_field_sphere() {
+        let s = Object::new_sdf(Sdf::sphere(1.0));
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_hits(&s.intersect(&r), &[4.0, 6.0]);
+        let r = Ray::new(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 0.0, 1.0));
+        assert_hits(&s.intersect(&r), &[-1.0, 1.0]);
+    }
+
+    #[test]
+    fn a_ray_misses_a_distance_field_sphere() {
+        let s = Object::new_sdf(Sdf::sphere(1.0));
+        let r = Ray::new(dvec3(0.0, 1.01, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(s.intersect(&r).count(), 0);
+    }
+
+    #[test]
+    fn a_transformed_distance_field_matches_the_analytic_shape() {
+        let transform = Transform::from_translation(dvec3(1.0, 0.5, 0.0)).with_scale(dvec3(2.0, 1.0, 1.0));
+        let sdf = Object::new_sdf(Sdf::torus(1.0, 0.25)).with_transform(transform);
+        let torus = Object::new_torus(1.0, 0.25).with_transform(transform);
+        let r = Ray::new(dvec3(-5.0, 0.6, 0.05), dvec3(1.0, 0.0, 0.02).normalize());
+        let expected: Vec<f64> = torus.intersect(&r).get_all().iter().map(|i| i.t()).collect();
+        assert_eq!(expected.len(), 4);
+        assert_hits(&sdf.intersect(&r), &expected);
+    }
+
+    #[test]
+    fn the_normal_is_the_gradient_of_the_distance() {
+        let s = Object::new_sdf(Sdf::sphere(1.0));
+        let p = dvec3(1.0, 1.0, 1.0).normalize();
+        assert!(s.normal_at(p).abs_diff_eq(p, EPSILON));
+        let c = Object::new_sdf(Sdf::cuboid(DVec3::ONE));
+        assert!(c.normal_at(dvec3(0.5, 1.0, -0.2)).abs_diff_eq(dvec3(0.0, 1.0, 0.0), EPSILON));
+    }
+
+    #[test]
+    fn a_subtraction_drills_through_a_box() {
+        let s = Object::new_sdf(Sdf::cuboid(DVec3::ONE).subtract(Sdf::sphere(1.2)));
+        let r = Ray::new(dvec3(0.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(s.intersect(&r).count(), 0);
+        let r = Ray::new(dvec3(0.9, 0.9, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(s.intersect(&r)[0].t(), 4.0);
+    }
+
+    #[test]
+    fn repeated_copies_are_found_within_the_bounds() {
+        let sdf = Sdf::sphere(1.0).repeat(dvec3(4.0, 0.0, 0.0));
+        let bounds = sdf.bounds();
+        assert_eq!(bounds.min(), dvec3(f64::NEG_INFINITY, -1.0, -1.0));
+        assert_eq!(bounds.max(), dvec3(f64::INFINITY, 1.0, 1.0));
+
+        let s = Object::new(Shape::Sdf(
+            SdfShape::new(sdf).with_bounds(BoundingBox::new(dvec3(-10.0, -1.0, -1.0), dvec3(10.0, 1.0, 1.0)))
+        ));
+        let r = Ray::new(dvec3(8.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_hits(&s.intersect(&r), &[4.0, 6.0]);
+        let r = Ray::new(dvec3(16.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(s.intersect(&r).count(), 0);
+        // Along the repetition, every copy is crossed
+        let r = Ray::new(dvec3(-20.0, 0.0, 0.0), dvec3(1.0, 0.0, 0.0));
+        assert_eq!(s.intersect(&r).count(), 10);
+    }
+
+    #[test]
+    fn the_bounds_of_a_smooth_union_contain_the_blend() {
+        let sdf = Sdf::sphere(1.0)
+            .smooth_union(Sdf::capsule(dvec3(0.0, 0.0, 0.0), dvec3(0.0, 3.0, 0.0), 0.5), 1.0);
+        let bounds = sdf.bounds();
+        assert_eq!(bounds.min(), dvec3(-1.25, -1.25, -1.25));
+        assert_eq!(bounds.max(), dvec3(1.25, 3.75, 1.25));
+    }
+
+    #[test]
+    fn a_distance_field_sphere_is_shaded_like_a_sphere() {
+        let scene_with = |sphere: Object| {
+            let mut scene = Scene::default();
+            scene.add_light(PointLight::new(dvec3(-10.0, 10.0, -10.0), Color::WHITE));
+            scene.add_object(sphere);
+            scene.add_object(Object::new_plane().with_transform(Transform::from_translation(dvec3(0.0, -1.0, 0.0))));
+            scene
+        };
+        let analytic = scene_with(Object::new_sphere());
+        let sdf = scene_with(Object::new_sdf(Sdf::sphere(1.0)));
+
+        let mut rng = Rng::new(5);
+        for _ in 0..50 {
+            let target = dvec3(rng.next_f64() * 4.0 - 2.0, rng.next_f64() * 2.0 - 1.5, 0.0);
+            let origin = dvec3(0.0, 0.5, -5.0);
+            let r = Ray::new(origin, (target - origin).normalize());
+            let expected = CpuRaytracer::color_at(&r, &analytic, DEFAULT_MAX_DEPTH, &mut Rng::default());
+            let c = CpuRaytracer::color_at(&r, &sdf, DEFAULT_MAX_DEPTH, &mut Rng::default());
+            assert_eq!(c, expected, "{}", target);
+        }
+    }
+}
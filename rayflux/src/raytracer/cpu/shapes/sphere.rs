@@ -1,4 +1,6 @@
-use glam::DVec3;
+use std::f64::consts::PI;
+
+use glam::{DVec2, DVec3};
 
 use crate::{common::{shapes::Sphere, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray}};
 
@@ -33,10 +35,23 @@ impl HittableShape for Sphere {
         )
     }
     
-    fn normal_at<'a>(&self, point: DVec3) -> DVec3 {
+    fn normal_at(&self, point: DVec3) -> DVec3 {
         // for a unit sphere at 0,0,0
         point.normalize()
     }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        // Azimuthal angle, in ]-pi, pi]
+        let theta = point.x.atan2(point.z);
+        // Polar angle, in [0, pi]
+        let phi = (point.y / point.length()).acos();
+        let raw_u = theta / (2.0 * PI);
+        // Make u increase counter-clockwise when seen from above
+        let u = 1.0 - (raw_u + 0.5);
+        // v is 0 at the south pole, 1 at the north pole
+        let v = 1.0 - phi / PI;
+        DVec2::new(u, v)
+    }
 }
 
 impl Sphere {
@@ -48,9 +63,7 @@ impl Sphere {
 
 #[cfg(test)]
 mod tests {
-    use std::f64::consts::PI;
-
-    use glam::dvec3;
+    use glam::{dvec2, dvec3};
     use crate::{common::Transform, raytracer::cpu::intersections::Hittable};
     use super::*;
 
@@ -189,6 +202,24 @@ mod tests {
     } 
 
     #[test]
+    fn using_a_spherical_mapping_on_a_3d_point() {
+        let s = Sphere::default();
+        let examples = [
+            (dvec3(0.0, 0.0, -1.0), dvec2(0.0, 0.5)),
+            (dvec3(1.0, 0.0, 0.0), dvec2(0.25, 0.5)),
+            (dvec3(0.0, 0.0, 1.0), dvec2(0.5, 0.5)),
+            (dvec3(-1.0, 0.0, 0.0), dvec2(0.75, 0.5)),
+            (dvec3(0.0, 1.0, 0.0), dvec2(0.5, 1.0)),
+            (dvec3(0.0, -1.0, 0.0), dvec2(0.5, 0.0)),
+            (dvec3(2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0, 0.0), dvec2(0.25, 0.75)),
+        ];
+        for (point, uv) in examples {
+            assert!(s.uv_at(point).abs_diff_eq(uv, EPSILON));
+        }
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
     fn computing_normal_on_a_translated_sphere() {
         let s = Object::new_sphere()
             .with_transform(
@@ -211,11 +242,11 @@ mod tests {
     //     assert!(n.abs_diff_eq(dvec3(0.0, 0.97014, -0.24254), EPSILON));
     // } 
 
-    // #[test]
-    // fn a_sphere_has_a_bounding_box() {
-    //     let s = Sphere::default();
-    //     let bb = s.bounds();
-    //     assert_eq!(bb.min(), dvec3(-1.0, -1.0, -1.0));
-    //     assert_eq!(bb.max(), dvec3(1.0, 1.0, 1.0));
-    // }
+    #[test]
+    fn a_sphere_has_a_bounding_box() {
+        let s = Sphere::default();
+        let bb = s.bounds();
+        assert_eq!(bb.min(), dvec3(-1.0, -1.0, -1.0));
+        assert_eq!(bb.max(), dvec3(1.0, 1.0, 1.0));
+    }
 }
\ No newline at end of file
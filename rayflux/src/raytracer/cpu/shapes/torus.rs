@@ -0,0 +1,160 @@
+use std::f64::consts::PI;
+
+use glam::{DVec2, DVec3};
+
+use crate::{common::{shapes::Torus, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, polynomial::solve_quartic, Ray}};
+
+impl HittableShape for Torus {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        // Solve for a unit direction from the point of the ray closest to the center,
+        // which keeps the coefficients small for rays starting far away and removes the cubic term
+        let length = ray.direction.length();
+        let direction = ray.direction / length;
+        let t0 = -ray.origin.dot(direction);
+        let origin = ray.origin + direction * t0;
+
+        let major2 = self.major_radius * self.major_radius;
+        let minor2 = self.minor_radius * self.minor_radius;
+        let e = origin.dot(origin) - major2 - minor2;
+        let f = origin.dot(direction);
+        let four_major2 = 4.0 * major2;
+
+        let roots = solve_quartic(
+            1.0,
+            4.0 * f,
+            2.0 * e + 4.0 * f * f + four_major2 * direction.y * direction.y,
+            4.0 * f * e + 2.0 * four_major2 * origin.y * direction.y,
+            e * e - four_major2 * (minor2 - origin.y * origin.y),
+        );
+
+        Intersections::new()
+        .with_intersections(
+            roots
+            .into_iter()
+            .map(|s| Intersection::new((s + t0) / length, object))
+            .collect()
+        )
+    }
+
+    fn normal_at(&self, point: DVec3) -> DVec3 {
+        // Gradient of (|p|^2 + R^2 - r^2)^2 - 4 R^2 (x^2 + z^2)
+        let major2 = self.major_radius * self.major_radius;
+        let minor2 = self.minor_radius * self.minor_radius;
+        let k = point.length_squared() - major2 - minor2;
+        DVec3::new(point.x * k, point.y * (k + 2.0 * major2), point.z * k).normalize()
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        // Angle around the y axis, increasing counter-clockwise when seen from above like on a sphere
+        let theta = point.x.atan2(point.z);
+        let u = 1.0 - (theta / (2.0 * PI) + 0.5);
+        // Angle around the tube, starting inside the ring
+        let distance = (point.x * point.x + point.z * point.z).sqrt() - self.major_radius;
+        let phi = point.y.atan2(-distance);
+        let v = 1.0 - (phi / (2.0 * PI) + 0.5);
+        DVec2::new(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{dvec2, dvec3};
+    use crate::raytracer::cpu::intersections::Hittable;
+    use super::*;
+
+    const EPSILON: f64 = 0.0001;
+
+    fn assert_hits(xs: &Intersections, expected: &[f64]) {
+        assert_eq!(xs.count(), expected.len());
+        for (intersection, t) in xs.get_all().iter().zip(expected) {
+            assert!((intersection.t() - t).abs() < EPSILON, "{} != {}", intersection.t(), t);
+        }
+    }
+
+    #[test]
+    fn a_ray_crosses_both_sides_of_the_ring() {
+        let t = Object::new_torus(1.0, 0.25);
+        let r = Ray::new(dvec3(-5.0, 0.0, 0.0), dvec3(1.0, 0.0, 0.0));
+        assert_hits(&t.intersect(&r), &[3.75, 4.25, 5.75, 6.25]);
+    }
+
+    #[test]
+    fn a_ray_passes_through_the_hole() {
+        let t = Object::new_torus(1.0, 0.25);
+        let r = Ray::new(dvec3(0.0, 5.0, 0.0), dvec3(0.0, -1.0, 0.0));
+        assert_eq!(t.intersect(&r).count(), 0);
+        let r = Ray::new(dvec3(0.5, 5.0, -0.3), dvec3(0.0, -1.0, 0.0));
+        assert_eq!(t.intersect(&r).count(), 0);
+    }
+
+    #[test]
+    fn a_ray_goes_through_the_tube() {
+        let t = Object::new_torus(1.0, 0.25);
+        let r = Ray::new(dvec3(1.0, 5.0, 0.0), dvec3(0.0, -1.0, 0.0));
+        assert_hits(&t.intersect(&r), &[4.75, 5.25]);
+        let r = Ray::new(dvec3(1.0, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_hits(&t.intersect(&r), &[4.25, 5.75]);
+    }
+
+    #[test]
+    fn a_grazing_ray_touches_the_top_of_the_ring() {
+        let t = Object::new_torus(1.0, 0.25);
+        let r = Ray::new(dvec3(-5.0, 0.25, 0.0), dvec3(1.0, 0.0, 0.0));
+        assert_hits(&t.intersect(&r), &[4.0, 4.0, 6.0, 6.0]);
+        let r = Ray::new(dvec3(-5.0, 0.2501, 0.0), dvec3(1.0, 0.0, 0.0));
+        assert_eq!(t.intersect(&r).count(), 0);
+    }
+
+    #[test]
+    fn a_grazing_ray_touches_the_outside_of_the_ring() {
+        let t = Object::new_torus(1.0, 0.25);
+        let r = Ray::new(dvec3(1.25, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_hits(&t.intersect(&r), &[5.0, 5.0]);
+        let r = Ray::new(dvec3(1.2501, 0.0, -5.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(t.intersect(&r).count(), 0);
+    }
+
+    #[test]
+    fn a_ray_from_far_away_keeps_its_precision() {
+        let t = Object::new_torus(1.0, 0.25);
+        let r = Ray::new(dvec3(-10000.0, 0.0, 0.0), dvec3(2.0, 0.0, 0.0));
+        assert_hits(&t.intersect(&r), &[4999.375, 4999.625, 5000.375, 5000.625]);
+    }
+
+    #[test]
+    fn the_normal_on_a_torus() {
+        let t = Torus::default();
+        let examples = [
+            (dvec3(1.25, 0.0, 0.0), dvec3(1.0, 0.0, 0.0)),
+            (dvec3(0.75, 0.0, 0.0), dvec3(-1.0, 0.0, 0.0)),
+            (dvec3(1.0, 0.25, 0.0), dvec3(0.0, 1.0, 0.0)),
+            (dvec3(0.0, -0.25, 1.0), dvec3(0.0, -1.0, 0.0)),
+            (dvec3(0.0, 0.0, -1.25), dvec3(0.0, 0.0, -1.0)),
+            (dvec3(1.0 + 0.25 * 0.6, 0.25 * 0.8, 0.0), dvec3(0.6, 0.8, 0.0)),
+        ];
+        for (point, normal) in examples {
+            assert!(t.normal_at(point).abs_diff_eq(normal, EPSILON), "{}", point);
+        }
+    }
+
+    #[test]
+    fn using_a_toroidal_mapping_on_a_3d_point() {
+        let t = Torus::default();
+        let examples = [
+            (dvec3(0.0, 0.0, -1.25), dvec2(0.0, 0.0)),
+            (dvec3(1.25, 0.0, 0.0), dvec2(0.25, 0.0)),
+            (dvec3(0.0, 0.25, 1.0), dvec2(0.5, 0.25)),
+            (dvec3(-0.75, 0.0, 0.0), dvec2(0.75, 0.5)),
+        ];
+        for (point, uv) in examples {
+            assert!(t.uv_at(point).abs_diff_eq(uv, EPSILON), "{} {}", point, t.uv_at(point));
+        }
+    }
+
+    #[test]
+    fn a_torus_has_a_bounding_box() {
+        let bb = Torus::new(2.0, 0.5).bounds();
+        assert_eq!(bb.min(), dvec3(-2.5, -0.5, -2.5));
+        assert_eq!(bb.max(), dvec3(2.5, 0.5, 2.5));
+    }
+}
@@ -0,0 +1,234 @@
+use glam::{DVec2, DVec3};
+
+use crate::{common::{shapes::{SmoothTriangle, Triangle}, Object}, raytracer::cpu::{intersections::{HittableShape, Intersection, Intersections}, Ray, EPSILON}};
+
+impl Triangle {
+    /// Möller–Trumbore intersection, returning the distance and the barycentric coordinates of the hit
+    pub(crate) fn hit(&self, ray: &Ray) -> Option<(f64, f64, f64)> {
+        let dir_cross_e2 = ray.direction.cross(self.e2());
+        let det = self.e1().dot(dir_cross_e2);
+        // The ray is parallel to the triangle, relative to the size of the triangle and of the ray
+        if det.abs() < EPSILON * self.e1().length() * self.e2().length() * ray.direction.length() {
+            return None;
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = ray.origin - self.p1();
+        let u = f * p1_to_origin.dot(dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(self.e1());
+        let v = f * ray.direction.dot(origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = f * self.e2().dot(origin_cross_e1);
+        Some((t, u, v))
+    }
+
+    /// Barycentric coordinates of a point of the triangle, the weights of p2 and p3
+    pub fn barycentric(&self, point: DVec3) -> DVec2 {
+        let p = point - self.p1();
+        let d00 = self.e1().dot(self.e1());
+        let d01 = self.e1().dot(self.e2());
+        let d11 = self.e2().dot(self.e2());
+        let d20 = p.dot(self.e1());
+        let d21 = p.dot(self.e2());
+        let denom = d00 * d11 - d01 * d01;
+        DVec2::new(
+            (d11 * d20 - d01 * d21) / denom,
+            (d00 * d21 - d01 * d20) / denom,
+        )
+    }
+}
+
+impl HittableShape for Triangle {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        match self.hit(ray) {
+            Some((t, u, v)) => Intersections::new()
+                .with_intersections(vec![Intersection::new_with_uv(t, object, u, v)]),
+            None => Intersections::new(),
+        }
+    }
+
+    fn normal_at(&self, _point: DVec3) -> DVec3 {
+        self.normal()
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        let uv = self.barycentric(point);
+        match self.texture_coords() {
+            Some([t1, t2, t3]) => *t2 * uv.x + *t3 * uv.y + *t1 * (1.0 - uv.x - uv.y),
+            None => uv,
+        }
+    }
+}
+
+impl HittableShape for SmoothTriangle {
+    fn intersect<'a>(&self, ray: &Ray, object: &'a Object) -> Intersections<'a> {
+        self.triangle().intersect(ray, object)
+    }
+
+    /// Normal of the flat triangle, the interpolated one needs the hit
+    fn normal_at(&self, point: DVec3) -> DVec3 {
+        self.triangle().normal_at(point)
+    }
+
+    fn normal_at_hit(&self, _point: DVec3, hit: &Intersection) -> DVec3 {
+        self.n2() * hit.u() + self.n3() * hit.v() + self.n1() * (1.0 - hit.u() - hit.v())
+    }
+
+    fn uv_at(&self, point: DVec3) -> DVec2 {
+        self.triangle().uv_at(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::dvec3;
+
+    use crate::{common::shapes::Shape, raytracer::cpu::intersections::{Hittable, IntersectionInfos}};
+
+    use super::*;
+
+    const EPSILON: f64 = 0.00001;
+
+    fn triangle() -> Triangle {
+        Triangle::new(dvec3(0.0, 1.0, 0.0), dvec3(-1.0, 0.0, 0.0), dvec3(1.0, 0.0, 0.0))
+    }
+
+    fn smooth_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            dvec3(0.0, 1.0, 0.0),
+            dvec3(-1.0, 0.0, 0.0),
+            dvec3(1.0, 0.0, 0.0),
+            dvec3(0.0, 1.0, 0.0),
+            dvec3(-1.0, 0.0, 0.0),
+            dvec3(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let t = triangle();
+        assert_eq!(t.p1(), dvec3(0.0, 1.0, 0.0));
+        assert_eq!(t.p2(), dvec3(-1.0, 0.0, 0.0));
+        assert_eq!(t.p3(), dvec3(1.0, 0.0, 0.0));
+        assert_eq!(t.e1(), dvec3(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2(), dvec3(1.0, -1.0, 0.0));
+        assert_eq!(t.normal(), dvec3(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_triangle() {
+        let t = triangle();
+        assert_eq!(t.normal_at(dvec3(0.0, 0.5, 0.0)), t.normal());
+        assert_eq!(t.normal_at(dvec3(-0.5, 0.75, 0.0)), t.normal());
+        assert_eq!(t.normal_at(dvec3(0.5, 0.25, 0.0)), t.normal());
+    }
+
+    #[test]
+    fn intersecting_a_ray_parallel_to_the_triangle() {
+        let o = Object::new(Shape::Triangle(triangle()));
+        let r = Ray::new(dvec3(0.0, -1.0, -2.0), dvec3(0.0, 1.0, 0.0));
+        assert_eq!(triangle().intersect(&r, &o).count(), 0);
+    }
+
+    #[test]
+    fn a_ray_misses_the_p1_p3_edge() {
+        let o = Object::new(Shape::Triangle(triangle()));
+        let r = Ray::new(dvec3(1.0, 1.0, -2.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(triangle().intersect(&r, &o).count(), 0);
+    }
+
+    #[test]
+    fn a_ray_misses_the_p1_p2_edge() {
+        let o = Object::new(Shape::Triangle(triangle()));
+        let r = Ray::new(dvec3(-1.0, 1.0, -2.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(triangle().intersect(&r, &o).count(), 0);
+    }
+
+    #[test]
+    fn a_ray_misses_the_p2_p3_edge() {
+        let o = Object::new(Shape::Triangle(triangle()));
+        let r = Ray::new(dvec3(0.0, -1.0, -2.0), dvec3(0.0, 0.0, 1.0));
+        assert_eq!(triangle().intersect(&r, &o).count(), 0);
+    }
+
+    #[test]
+    fn a_ray_strikes_a_triangle() {
+        let o = Object::new(Shape::Triangle(triangle()));
+        let r = Ray::new(dvec3(0.0, 0.5, -2.0), dvec3(0.0, 0.0, 1.0));
+        let xs = triangle().intersect(&r, &o);
+        assert_eq!(xs.count(), 1);
+        assert_eq!(xs[0].t(), 2.0);
+    }
+
+    #[test]
+    fn a_ray_strikes_a_tiny_triangle() {
+        let t = Object::new_triangle(dvec3(0.0, 1e-3, 0.0), dvec3(-1e-3, 0.0, 0.0), dvec3(1e-3, 0.0, 0.0));
+        let r = Ray::new(dvec3(0.0, 5e-4, -2.0), dvec3(0.0, 0.0, 1.0));
+        let xs = t.intersect(&r);
+        assert_eq!(xs.count(), 1);
+        assert!((xs[0].t() - 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn an_intersection_can_encapsulate_u_and_v() {
+        let o = Object::new(Shape::Triangle(triangle()));
+        let i = Intersection::new_with_uv(3.5, &o, 0.2, 0.4);
+        assert_eq!(i.u(), 0.2);
+        assert_eq!(i.v(), 0.4);
+    }
+
+    #[test]
+    fn an_intersection_with_a_smooth_triangle_stores_u_and_v() {
+        let o = Object::new(Shape::SmoothTriangle(smooth_triangle()));
+        let r = Ray::new(dvec3(-0.2, 0.3, -2.0), dvec3(0.0, 0.0, 1.0));
+        let xs = smooth_triangle().intersect(&r, &o);
+        assert!((xs[0].u() - 0.45).abs() < EPSILON);
+        assert!((xs[0].v() - 0.25).abs() < EPSILON);
+    }
+
+    #[test]
+    fn a_smooth_triangle_uses_u_and_v_to_interpolate_the_normal() {
+        let o = Object::new(Shape::SmoothTriangle(smooth_triangle()));
+        let i = Intersection::new_with_uv(1.0, &o, 0.45, 0.25);
+        let n = o.normal_at_hit(dvec3(0.0, 0.0, 0.0), &i);
+        assert!(n.abs_diff_eq(dvec3(-0.5547, 0.83205, 0.0), EPSILON));
+    }
+
+    #[test]
+    fn preparing_the_normal_on_a_smooth_triangle() {
+        let o = Object::new(Shape::SmoothTriangle(smooth_triangle()));
+        let i = Intersection::new_with_uv(1.0, &o, 0.45, 0.25);
+        let r = Ray::new(dvec3(-0.2, 0.3, -2.0), dvec3(0.0, 0.0, 1.0));
+        let xs = Intersections::new().with_intersections(vec![i]);
+        let infos = IntersectionInfos::new(&xs, 0, &r);
+        assert!(infos.normal.abs_diff_eq(dvec3(-0.5547, 0.83205, 0.0), EPSILON));
+    }
+
+    #[test]
+    fn barycentric_coordinates_are_the_texture_coordinates() {
+        let t = triangle();
+        assert!(t.uv_at(dvec3(0.0, 1.0, 0.0)).abs_diff_eq(DVec2::new(0.0, 0.0), EPSILON));
+        assert!(t.uv_at(dvec3(-1.0, 0.0, 0.0)).abs_diff_eq(DVec2::new(1.0, 0.0), EPSILON));
+        assert!(t.uv_at(dvec3(1.0, 0.0, 0.0)).abs_diff_eq(DVec2::new(0.0, 1.0), EPSILON));
+        assert!(t.uv_at(dvec3(-0.2, 0.3, 0.0)).abs_diff_eq(DVec2::new(0.45, 0.25), EPSILON));
+    }
+
+    #[test]
+    fn texture_coordinates_are_interpolated_across_the_triangle() {
+        let t = triangle().with_texture_coords(
+            DVec2::new(0.5, 1.0),
+            DVec2::new(0.0, 0.0),
+            DVec2::new(1.0, 0.0),
+        );
+        assert!(t.uv_at(dvec3(0.0, 1.0, 0.0)).abs_diff_eq(DVec2::new(0.5, 1.0), EPSILON));
+        assert!(t.uv_at(dvec3(0.0, 0.0, 0.0)).abs_diff_eq(DVec2::new(0.5, 0.0), EPSILON));
+        assert!(t.uv_at(dvec3(0.5, 0.5, 0.0)).abs_diff_eq(DVec2::new(0.75, 0.5), EPSILON));
+    }
+}
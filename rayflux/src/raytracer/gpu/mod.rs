@@ -18,7 +18,7 @@ pub struct GpuRaytracer {
 }
 
 impl RaytracerImpl for GpuRaytracer {   
-    fn output(&self) -> RaytracerOutput {
+    fn output(&self) -> RaytracerOutput<'_> {
         RaytracerOutput::WgpuTexture(&self.render_target)
     }
 }
@@ -27,10 +27,10 @@ impl GpuRaytracer {
     pub fn new(context: &GpuContext, scene: &Scene, size: UVec2) -> Self {
         let device = context.device();
         
-        let render_target = Self::create_render_target(&device, size);
+        let render_target = Self::create_render_target(device, size);
 
         let camera = scene.camera();
-        let compute_pipeline = Self::create_compute_pipeline(&device, &render_target, camera);   
+        let compute_pipeline = Self::create_compute_pipeline(device, &render_target, camera);   
 
         GpuRaytracer {
             render_target,
@@ -213,8 +213,8 @@ impl GpuRaytracer {
         compute_pass.set_bind_group(0, &self.compute_pipeline.image_bind_group, &[]);
         compute_pass.set_bind_group(1, &self.compute_pipeline.camera_bind_group, &[]);
         compute_pass.dispatch_workgroups(
-            (self.render_target.size.x + 7) / 8, 
-            (self.render_target.size.y + 7) / 8, 
+            self.render_target.size.x.div_ceil(8), 
+            self.render_target.size.y.div_ceil(8), 
             1
         );
 
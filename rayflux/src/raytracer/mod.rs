@@ -7,18 +7,13 @@ use crate::{common::Texture, raytracer::{cpu::CpuRaytracer, gpu::GpuRaytracer}};
 pub mod cpu;
 pub mod gpu;
 
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Clone, Debug, Default)]
 pub enum RaytracerType {
     Cpu,
+    #[default]
     Gpu,
 }
 
-impl Default for RaytracerType {
-    fn default() -> Self {
-        Self::Gpu
-    }
-}
-
 impl fmt::Display for RaytracerType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -34,11 +29,11 @@ pub enum Raytracer {
 }
 
 pub trait RaytracerImpl {
-    fn output(&self) -> RaytracerOutput;
+    fn output(&self) -> RaytracerOutput<'_>;
 }
 
 impl RaytracerImpl for Raytracer {    
-    fn output(&self) -> RaytracerOutput {
+    fn output(&self) -> RaytracerOutput<'_> {
         match self {
             Raytracer::Cpu(cpu) => cpu.output(),
             Raytracer::Gpu(gpu) => gpu.output(),
@@ -1,20 +1,21 @@
-use std::{path::Path, time::Instant};
+use std::{path::{Path, PathBuf}, time::Instant};
 
 use glam::{DVec3, UVec2};
 use image::{ImageBuffer, Rgba};
 
 use rayflux::{
-    common::{GpuContext, Object, Scene, Texture, Transform}, 
+    common::{color::Color, GpuContext, Material, Object, ObjFile, PointLight, Scene, Texture, Transform}, 
     raytracer::{cpu::CpuRaytracer, gpu::GpuRaytracer, RaytracerType}
 };
 
 pub struct App {
     raytracer_type: RaytracerType,
+    obj_path: Option<PathBuf>,
 }
 
 impl App {
-    pub fn new(raytracer_type: RaytracerType) -> Self {
-        Self { raytracer_type }
+    pub fn new(raytracer_type: RaytracerType, obj_path: Option<PathBuf>) -> Self {
+        Self { raytracer_type, obj_path }
     }
 
     pub fn run(&mut self) {
@@ -22,21 +23,55 @@ impl App {
 
         // Create a scene
         let mut scene = Scene::default();
-        scene.add_object(
-            Object::new_sphere()
-            .with_transform(
-                Transform::from_translation(
-                    DVec3::new(1.0, 0.0, 0.0)
-                )
-            )
-        );
+        match &self.obj_path {
+            Some(path) => {
+                let file = match ObjFile::load(path) {
+                    Ok(file) => file,
+                    Err(error) => {
+                        eprintln!("Failed to read OBJ file {}: {}", path.display(), error);
+                        std::process::exit(1);
+                    },
+                };
+                for warning in file.warnings() {
+                    println!("Skipped {}", warning);
+                }
+                for object in file.into_objects(&Material::new()) {
+                    scene.add_object(object);
+                }
+            },
+            None => {
+                scene.add_object(
+                    Object::new_sphere()
+                    .with_transform(
+                        Transform::from_translation(
+                            DVec3::new(1.0, 0.0, 0.0)
+                        )
+                    )
+                    .with_material(
+                        Material::new()
+                        .with_color(Color::new(1.0, 0.2, 0.2))
+                    )
+                );
+
+                scene.add_object(
+                    Object::new_sphere()
+                    .with_transform(
+                        Transform::from_translation(
+                            DVec3::new(-1.0, 0.0, 0.0)
+                        )
+                    )
+                    .with_material(
+                        Material::new()
+                        .with_color(Color::new(0.2, 0.2, 1.0))
+                    )
+                );
+            },
+        }
 
-        scene.add_object(
-            Object::new_sphere()
-            .with_transform(
-                Transform::from_translation(
-                    DVec3::new(-1.0, 0.0, 0.0)
-                )
+        scene.add_light(
+            PointLight::new(
+                DVec3::new(-10.0, 10.0, 10.0), 
+                Color::WHITE
             )
         );
 
@@ -46,7 +81,7 @@ impl App {
 
         // Prepare objects
         for object in scene.objects_mut().iter_mut() {
-            object.transform_mut().update_matrix();
+            object.update_matrix();
         }
 
         let now = Instant::now();
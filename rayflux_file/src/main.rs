@@ -1,5 +1,7 @@
 mod app;
 
+use std::path::PathBuf;
+
 use clap::Parser;
 use rayflux::raytracer::RaytracerType;
 
@@ -9,10 +11,13 @@ use crate::app::App;
 struct Args {
     #[arg(long, default_value = "cpu", value_enum)]
     raytracer: RaytracerType,
+    /// Render the triangles of an OBJ file instead of the default spheres
+    #[arg(long)]
+    obj: Option<PathBuf>,
 }
 
 fn main() {
     let args = Args::parse();
-    let mut app = App::new(args.raytracer);
+    let mut app = App::new(args.raytracer, args.obj);
     app.run();
 }
@@ -123,7 +123,7 @@ impl App {
         // Update raytracer output size
         let raytracer = self.raytracer.as_mut().unwrap();
         match raytracer {
-            Raytracer::Cpu(cpu_raytracer) => todo!(),
+            Raytracer::Cpu(_cpu_raytracer) => todo!(),
             Raytracer::Gpu(gpu_raytracer) => gpu_raytracer.resize(device, size),
         }
 
@@ -208,17 +208,17 @@ impl ApplicationHandler for App {
             WindowEvent::CursorMoved { position, .. } => {
                 self.inputs.on_mouse_move(Vec2::new(position.x as f32, position.y as f32));
             },
-            WindowEvent::MouseWheel { device_id, delta, phase } => {
+            WindowEvent::MouseWheel { delta, .. } => {
                 match delta {
                     MouseScrollDelta::LineDelta(delta, _) => self.inputs.on_mouse_wheel(delta),
                     MouseScrollDelta::PixelDelta(_) => todo!(),
                 }
             },
-            WindowEvent::MouseInput { device_id, state, button } => match state {
+            WindowEvent::MouseInput { state, button, .. } => match state {
                 ElementState::Pressed => self.inputs.on_mouse_button_down(button),
                 ElementState::Released => self.inputs.on_mouse_button_up(button),
             },
-            WindowEvent::KeyboardInput { device_id, event, is_synthetic } => match event.physical_key {
+            WindowEvent::KeyboardInput { event, .. } => match event.physical_key {
                 winit::keyboard::PhysicalKey::Code(key) => {
                     match event.state {
                         ElementState::Pressed => self.inputs.on_key_down(key),
@@ -238,6 +238,7 @@ impl ApplicationHandler for App {
 pub struct Frame {
     pub surface_texture: wgpu::SurfaceTexture,
     pub surface_view: wgpu::TextureView,
+    #[allow(dead_code)]
     pub size: UVec2,
     pub command_encoder: wgpu::CommandEncoder,
 }
@@ -262,7 +263,7 @@ impl FullScreenRenderer {
         let size = window.inner_size();
 
         let surface = context.instance().create_surface(window.clone()).unwrap();
-        let cap = surface.get_capabilities(&context.adapter());
+        let cap = surface.get_capabilities(context.adapter());
         let surface_format = cap.formats[0];
 
         let surface_size = UVec2 { x: size.width, y: size.height };     
@@ -274,7 +275,7 @@ impl FullScreenRenderer {
             RaytracerOutput::Image => todo!(),
         };
         
-        let render_pipeline = Self::create_render_pipeline(&device, surface_format, render_target);
+        let render_pipeline = Self::create_render_pipeline(device, surface_format, render_target);
 
         let renderer = FullScreenRenderer { 
             window,
@@ -553,7 +554,7 @@ impl GuiRenderer {
                     context.device(), 
                     context.queue(), 
                     *id, 
-                    &image_delta
+                    image_delta
                 );
         }
 